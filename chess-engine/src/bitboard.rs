@@ -0,0 +1,311 @@
+//! Sets of squares stored as the bits of a `u64`, and precomputed tables of
+//! the squares each piece attacks.
+//!
+//! The square at `Position { file, rank }` is bit `rank * 8 + file`, so bit 0
+//! is a8 and bit 63 is h1.
+
+use std::ops;
+
+use crate::{Color, Position};
+
+mod magic;
+
+pub(crate) use magic::{bishop_attacks, rook_attacks};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self(0);
+    /// The squares of the same color as a8
+    pub const LIGHT_SQUARES: Self = Self(0x55aa_55aa_55aa_55aa);
+    pub const DARK_SQUARES: Self = Self(!Self::LIGHT_SQUARES.0);
+
+    pub fn contains(self, pos: Position) -> bool {
+        self.0 & Self::from(pos).0 != 0
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+    /// Returns the square with the lowest index, i.e. the one closest to a8
+    pub fn first(self) -> Option<Position> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(position(self.0.trailing_zeros() as usize))
+        }
+    }
+    /// Returns the square with the highest index, i.e. the one closest to h1
+    pub fn last(self) -> Option<Position> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(position(63 - self.0.leading_zeros() as usize))
+        }
+    }
+}
+
+impl From<Position> for Bitboard {
+    fn from(pos: Position) -> Self {
+        Self(1 << index(pos))
+    }
+}
+
+/// Iterates over the squares in the set, from a8 to h1
+impl Iterator for Bitboard {
+    type Item = Position;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(pos)
+    }
+}
+
+impl ops::BitAnd for Bitboard {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl ops::BitOr for Bitboard {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitXor for Bitboard {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl ops::Not for Bitboard {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl ops::BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl ops::BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl ops::BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+pub(crate) fn index(pos: Position) -> usize {
+    pos.rank() as usize * 8 + pos.file() as usize
+}
+
+pub(crate) fn position(index: usize) -> Position {
+    Position::new_unchecked(index as u8 % 8, index as u8 / 8)
+}
+
+pub(crate) fn knight_attacks(pos: Position) -> Bitboard {
+    Bitboard(KNIGHT_ATTACKS[index(pos)])
+}
+
+pub(crate) fn king_attacks(pos: Position) -> Bitboard {
+    Bitboard(KING_ATTACKS[index(pos)])
+}
+
+/// Returns the squares a pawn with the color `color` standing on `pos` attacks
+pub(crate) fn pawn_attacks(color: Color, pos: Position) -> Bitboard {
+    match color {
+        Color::White => Bitboard(WHITE_PAWN_ATTACKS[index(pos)]),
+        Color::Black => Bitboard(BLACK_PAWN_ATTACKS[index(pos)]),
+    }
+}
+
+pub(crate) fn queen_attacks(pos: Position, occupied: Bitboard) -> Bitboard {
+    rook_attacks(pos, occupied) | bishop_attacks(pos, occupied)
+}
+
+/// Returns the squares a rook on `pos` attacks, stopping at (and including)
+/// the first occupied square in each direction. Slower than `rook_attacks`,
+/// which looks up the result of this function.
+pub(crate) fn classical_rook_attacks(pos: Position, occupied: Bitboard) -> Bitboard {
+    ROOK_DIRECTIONS
+        .iter()
+        .fold(Bitboard::EMPTY, |attacks, &dir| {
+            attacks | ray_attacks(dir, pos, occupied)
+        })
+}
+
+/// Returns the squares a bishop on `pos` attacks, stopping at (and including)
+/// the first occupied square in each direction. Slower than `bishop_attacks`,
+/// which looks up the result of this function.
+pub(crate) fn classical_bishop_attacks(pos: Position, occupied: Bitboard) -> Bitboard {
+    BISHOP_DIRECTIONS
+        .iter()
+        .fold(Bitboard::EMPTY, |attacks, &dir| {
+            attacks | ray_attacks(dir, pos, occupied)
+        })
+}
+
+/// Returns the squares between `a` and `b`, not including them, if they are on
+/// the same rank, file or diagonal. Otherwise returns no squares.
+pub(crate) fn between(a: Position, b: Position) -> Bitboard {
+    Bitboard(BETWEEN[index(a)][index(b)])
+}
+
+/// Returns the squares on the rank, file or diagonal through `a` and `b`, from
+/// edge to edge, if there is one. Otherwise returns no squares.
+pub(crate) fn line(a: Position, b: Position) -> Bitboard {
+    Bitboard(LINE[index(a)][index(b)])
+}
+
+/// Indices into `RAYS`. Directions with an even index go towards higher
+/// square indices.
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 2, 3];
+const BISHOP_DIRECTIONS: [usize; 4] = [4, 5, 6, 7];
+const DIRECTIONS: [(i8, i8); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+];
+
+fn ray_attacks(dir: usize, pos: Position, occupied: Bitboard) -> Bitboard {
+    let ray = Bitboard(RAYS[dir][index(pos)]);
+    let blocker = if dir.is_multiple_of(2) {
+        (ray & occupied).first()
+    } else {
+        (ray & occupied).last()
+    };
+    match blocker {
+        Some(blocker) => ray ^ Bitboard(RAYS[dir][index(blocker)]),
+        None => ray,
+    }
+}
+
+const KNIGHT_ATTACKS: [u64; 64] = leaper_attacks(&[
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+    (1, 2),
+    (2, 1),
+]);
+const KING_ATTACKS: [u64; 64] = leaper_attacks(&[
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+]);
+const WHITE_PAWN_ATTACKS: [u64; 64] = leaper_attacks(&[(-1, -1), (1, -1)]);
+const BLACK_PAWN_ATTACKS: [u64; 64] = leaper_attacks(&[(-1, 1), (1, 1)]);
+
+/// For every square, every square in each direction, not including the square
+/// itself
+const RAYS: [[u64; 64]; 8] = {
+    let mut rays = [[0; 64]; 8];
+    let mut dir = 0;
+    while dir < 8 {
+        let mut sq = 0;
+        while sq < 64 {
+            let (delta_file, delta_rank) = DIRECTIONS[dir];
+            let mut file = (sq % 8) as i8 + delta_file;
+            let mut rank = (sq / 8) as i8 + delta_rank;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                rays[dir][sq] |= 1 << (rank * 8 + file);
+                file += delta_file;
+                rank += delta_rank;
+            }
+            sq += 1;
+        }
+        dir += 1;
+    }
+    rays
+};
+
+static BETWEEN: [[u64; 64]; 64] = {
+    let mut between = [[0; 64]; 64];
+    let mut dir = 0;
+    while dir < 8 {
+        let mut a = 0;
+        while a < 64 {
+            let (delta_file, delta_rank) = DIRECTIONS[dir];
+            let mut squares = 0;
+            let mut file = (a % 8) as i8 + delta_file;
+            let mut rank = (a / 8) as i8 + delta_rank;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                let b = (rank * 8 + file) as usize;
+                between[a][b] = squares;
+                squares |= 1 << b;
+                file += delta_file;
+                rank += delta_rank;
+            }
+            a += 1;
+        }
+        dir += 1;
+    }
+    between
+};
+
+static LINE: [[u64; 64]; 64] = {
+    let mut line = [[0; 64]; 64];
+    let mut dir = 0;
+    while dir < 8 {
+        let mut a = 0;
+        while a < 64 {
+            // Directions come in pairs of opposite directions
+            let full_line = RAYS[dir][a] | RAYS[dir ^ 1][a] | 1 << a;
+            let mut rays = RAYS[dir][a];
+            while rays != 0 {
+                let b = rays.trailing_zeros() as usize;
+                line[a][b] = full_line;
+                rays &= rays - 1;
+            }
+            a += 1;
+        }
+        dir += 1;
+    }
+    line
+};
+
+/// Computes the attacks of a piece that jumps by `deltas` from every square
+const fn leaper_attacks(deltas: &[(i8, i8)]) -> [u64; 64] {
+    let mut attacks = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut i = 0;
+        while i < deltas.len() {
+            let file = (sq % 8) as i8 + deltas[i].0;
+            let rank = (sq / 8) as i8 + deltas[i].1;
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                attacks[sq] |= 1 << (rank * 8 + file);
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    attacks
+}
@@ -1,8 +1,22 @@
-use std::{fmt, ops};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops,
+};
 
-use crate::{piece, Color, Piece, Position};
+use crate::{
+    bitboard::{self, Bitboard},
+    piece, Color, Move, Piece, Position,
+};
 
 mod fen;
+mod make_move;
+mod movegen;
+mod see;
+mod zobrist;
+
+pub use make_move::MoveRecord;
+use movegen::MoveGen;
 
 /// Represents the state of a chess board.
 ///
@@ -19,6 +33,13 @@ pub struct Board {
     pub(crate) en_passant_square: Option<Position>,
     pub(crate) halfmove_counter: u16,
     pub(crate) move_number: u16,
+    /// The squares occupied by each kind of piece, indexed by `piece::Kind`,
+    /// kept in sync with `tiles`
+    pub(crate) pieces: [Bitboard; 6],
+    /// The squares occupied by each color, indexed by `Color`
+    pub(crate) colors: [Bitboard; 2],
+    /// Kept equal to `zobrist()` by every method that changes the board
+    pub(crate) zobrist_key: u64,
 }
 
 impl Board {
@@ -34,9 +55,10 @@ impl Board {
     /// `next_to_move` was black before call. Also increments
     /// `halfmove_counter`
     pub fn switch_next_to_move(&mut self) {
-        self.halfmove_counter += 1;
+        self.zobrist_key ^= zobrist::white_to_move_key();
+        self.halfmove_counter = self.halfmove_counter.saturating_add(1);
         if self.next_to_move() == Color::Black {
-            self.move_number += 1;
+            self.move_number = self.move_number.saturating_add(1);
         }
         self.next_to_move = self.next_to_move.other();
     }
@@ -46,6 +68,9 @@ impl Board {
         self.en_passant_square
     }
     pub fn set_en_passant_square(&mut self, eps: Option<Position>) {
+        for eps in self.en_passant_square.iter().chain(eps.iter()) {
+            self.zobrist_key ^= zobrist::en_passant_key(eps.file());
+        }
         self.en_passant_square = eps;
     }
     pub fn can_castle_kingside(&self, color: Color) -> bool {
@@ -63,39 +88,228 @@ impl Board {
     /// Marks that `color` can no longer castle on the kingside. Can be called
     /// even if it was not possible before calling (but will have no effect)
     pub fn cannot_castle_kingside(&mut self, color: Color) {
-        match color {
-            Color::White => self.can_castle_white_kingside = false,
-            Color::Black => self.can_castle_black_kingside = false,
+        let (right, index) = match color {
+            Color::White => (&mut self.can_castle_white_kingside, 0),
+            Color::Black => (&mut self.can_castle_black_kingside, 2),
+        };
+        if *right {
+            *right = false;
+            self.zobrist_key ^= zobrist::castling_key(index);
         }
     }
     /// Marks that `color` can no longer castle on the queenside. Can be called
     /// even if it was not possible before calling (but will have no effect)
     pub fn cannot_castle_queenside(&mut self, color: Color) {
-        match color {
-            Color::White => self.can_castle_white_queenside = false,
-            Color::Black => self.can_castle_black_queenside = false,
+        let (right, index) = match color {
+            Color::White => (&mut self.can_castle_white_queenside, 1),
+            Color::Black => (&mut self.can_castle_black_queenside, 3),
+        };
+        if *right {
+            *right = false;
+            self.zobrist_key ^= zobrist::castling_key(index);
         }
     }
+    /// The number of halfmoves (moves by either player) made since the last
+    /// capture or pawn move
+    pub fn halfmove_counter(&self) -> u16 {
+        self.halfmove_counter
+    }
     /// Sets the halvmove counter to zero
     pub fn reset_halfmove_counter(&mut self) {
         self.halfmove_counter = 0;
     }
     /// Returns the position of the king with the color `color`.
     pub fn get_king_position(&self, color: Color) -> Position {
-        let mut pos = Position::new_unchecked(0, 0);
-        while self[pos]
-            != Some(Piece {
-                color,
-                kind: piece::Kind::King,
-            })
-        {
-            if pos.file() == 7 {
-                pos = Position::new_unchecked(0, pos.rank() + 1)
-            } else {
-                pos = Position::new_unchecked(pos.file() + 1, pos.rank())
+        self.bitboard(color, piece::Kind::King).first().unwrap()
+    }
+    /// Returns the squares occupied by pieces of `kind` and `color`
+    pub(crate) fn bitboard(&self, color: Color, kind: piece::Kind) -> Bitboard {
+        self.pieces[kind as usize] & self.colors[color as usize]
+    }
+    /// Returns the squares occupied by pieces of `color`
+    pub(crate) fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.colors[color as usize]
+    }
+    pub(crate) fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+    /// Returns the pieces of `color` that attack `pos`, as if the squares in
+    /// `occupied` were the occupied ones
+    pub(crate) fn attackers(&self, pos: Position, color: Color, occupied: Bitboard) -> Bitboard {
+        use piece::Kind::*;
+
+        let pieces = |kind: piece::Kind| self.pieces[kind as usize];
+        let attackers = bitboard::knight_attacks(pos) & pieces(Knight)
+            | bitboard::king_attacks(pos) & pieces(King)
+            | bitboard::pawn_attacks(color.other(), pos) & pieces(Pawn)
+            | bitboard::rook_attacks(pos, occupied) & (pieces(Rook) | pieces(Queen))
+            | bitboard::bishop_attacks(pos, occupied) & (pieces(Bishop) | pieces(Queen));
+        attackers & self.color_bitboard(color)
+    }
+    /// Whether the king of the player to move is attacked
+    pub fn is_check(&self) -> bool {
+        let king = self.get_king_position(self.next_to_move);
+        !self
+            .attackers(king, self.next_to_move.other(), self.occupied())
+            .is_empty()
+    }
+    /// Returns the positions of the pieces that attack the king of the player
+    /// to move. There are at most two.
+    pub fn checkers(&self) -> Vec<Position> {
+        let king = self.get_king_position(self.next_to_move);
+        self.attackers(king, self.next_to_move.other(), self.occupied())
+            .collect()
+    }
+    /// Whether the player to move is in check and has no legal moves
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+    /// Whether the player to move is not in check but has no legal moves
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+    /// Returns every legal move for the player whose turn it is. Moves that
+    /// lead to a pawn promotion are included once for every kind in
+    /// `piece::Kind::PROMOTIONS`.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.move_gen(self.next_to_move).legal_moves(&mut moves);
+        moves
+    }
+    /// Returns every legal capture, including en passant, and every legal
+    /// promotion for the player whose turn it is. Promotions are included as
+    /// in `legal_moves`.
+    pub fn captures(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.move_gen(self.next_to_move).captures(&mut moves);
+        moves
+    }
+    pub(crate) fn move_gen(&self, color: Color) -> MoveGen<'_> {
+        MoveGen::new(self, color)
+    }
+    /// Whether `color` lacks the material to ever checkmate the opponent, e.g.
+    /// to decide if losing on time should be a draw. This is the case if
+    /// `color` has
+    /// - only a king
+    /// - a king and a knight, and the opponent has nothing but a king and
+    ///   queens. Any other piece of the opponent could block the square its
+    ///   king would escape to.
+    /// - a king and bishops, all on squares of the same color, and there are
+    ///   no pawns or knights on the board. Rooks and queens of the opponent
+    ///   don't matter, since bishops of one color can't cover the squares next
+    ///   to the king that such pieces could block.
+    pub fn has_insufficient_material(&self, color: Color) -> bool {
+        use piece::Kind::*;
+
+        let pieces = |kind| self.pieces[kind as usize];
+        let own = self.color_bitboard(color);
+        if !(own & (pieces(Pawn) | pieces(Rook) | pieces(Queen))).is_empty() {
+            return false;
+        }
+        if !(own & pieces(Knight)).is_empty() {
+            let opponent = self.color_bitboard(color.other());
+            return own.count() <= 2 && (opponent & !(pieces(King) | pieces(Queen))).is_empty();
+        }
+        if !(own & pieces(Bishop)).is_empty() {
+            let bishops = pieces(Bishop);
+            let same_square_color = (bishops & Bitboard::LIGHT_SQUARES).is_empty()
+                || (bishops & Bitboard::DARK_SQUARES).is_empty();
+            return same_square_color && (pieces(Pawn) | pieces(Knight)).is_empty();
+        }
+        true
+    }
+    /// Returns the Zobrist key of the position, computed from scratch. The key
+    /// covers the pieces, the player to move, the castling rights and the file
+    /// of the en passant square. Equal positions always have the same key, and
+    /// different positions have the same key only with a tiny probability.
+    ///
+    /// The key is also maintained incrementally while moves are made, see
+    /// `zobrist_key`.
+    pub fn zobrist(&self) -> u64 {
+        let mut key = 0;
+        for pos in self.occupied() {
+            key ^= zobrist::piece_key(self[pos].unwrap(), pos);
+        }
+        if self.next_to_move == Color::White {
+            key ^= zobrist::white_to_move_key();
+        }
+        for (index, &right) in self.castling().iter().enumerate() {
+            if right {
+                key ^= zobrist::castling_key(index);
+            }
+        }
+        if let Some(eps) = self.en_passant_square {
+            key ^= zobrist::en_passant_key(eps.file());
+        }
+        key
+    }
+    /// Returns the Zobrist key of the position without recomputing it. Always
+    /// equal to `zobrist()`.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+    /// Returns the key used for the position by Polyglot opening books. It
+    /// differs from `zobrist_key` in that the en passant square only counts if
+    /// a pawn of the player to move stands next to the pawn that just moved.
+    pub fn polyglot_key(&self) -> u64 {
+        match self.en_passant_square {
+            Some(eps) if !self.pawn_next_to_en_passant(eps) => {
+                self.zobrist_key ^ zobrist::en_passant_key(eps.file())
             }
+            _ => self.zobrist_key,
         }
-        pos
+    }
+    /// Identifies a position for the purpose of detecting repetitions. Two
+    /// positions are the same if the same player is to move, the same pieces
+    /// occupy the same squares, and the possible moves (including castling and
+    /// en passant captures) are the same.
+    pub(crate) fn position_key(&self) -> u64 {
+        match self.en_passant_square {
+            Some(eps) if !self.can_en_passant(eps) => {
+                self.zobrist_key ^ zobrist::en_passant_key(eps.file())
+            }
+            _ => self.zobrist_key,
+        }
+    }
+    /// The castling rights in the order white kingside, white queenside, black
+    /// kingside, black queenside
+    fn castling(&self) -> [bool; 4] {
+        [
+            self.can_castle_white_kingside,
+            self.can_castle_white_queenside,
+            self.can_castle_black_kingside,
+            self.can_castle_black_queenside,
+        ]
+    }
+    /// Puts `piece` on `pos`, returning the piece that was there before
+    fn set_tile(&mut self, pos: Position, piece: Option<Piece>) -> Option<Piece> {
+        let tile = &mut self.tiles[pos.rank() as usize][pos.file() as usize];
+        let old = std::mem::replace(tile, piece);
+        for &piece in old.iter().chain(piece.iter()) {
+            self.pieces[piece.kind as usize] ^= Bitboard::from(pos);
+            self.colors[piece.color as usize] ^= Bitboard::from(pos);
+            self.zobrist_key ^= zobrist::piece_key(piece, pos);
+        }
+        old
+    }
+    /// Returns the pawns of the player to move that stand next to the pawn
+    /// that moved over `eps`
+    fn pawns_next_to_en_passant(&self, eps: Position) -> impl Iterator<Item = Position> + '_ {
+        let pawn = Piece::new(self.next_to_move, piece::Kind::Pawn);
+        let rank = eps.rank() as i8 - self.next_to_move.forwards();
+        [-1, 1]
+            .iter()
+            .filter_map(move |delta_file| Position::new_i8(eps.file() as i8 + delta_file, rank))
+            .filter(move |&from| self[from] == Some(pawn))
+    }
+    fn pawn_next_to_en_passant(&self, eps: Position) -> bool {
+        self.pawns_next_to_en_passant(eps).next().is_some()
+    }
+    /// Whether the player to move can legally capture en passant on `eps`
+    fn can_en_passant(&self, eps: Position) -> bool {
+        let pawn = Piece::new(self.next_to_move, piece::Kind::Pawn);
+        self.pawns_next_to_en_passant(eps)
+            .any(|from| pawn.moves(self, from).any(|to| to == eps))
     }
 }
 
@@ -106,9 +320,9 @@ impl ops::Index<Position> for Board {
     }
 }
 
-impl ops::IndexMut<Position> for Board {
-    fn index_mut(&mut self, p: Position) -> &mut Self::Output {
-        &mut self.tiles[p.rank() as usize][p.file() as usize]
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zobrist_key.hash(state);
     }
 }
 
@@ -1,47 +1,23 @@
 use std::str::FromStr;
 
-use crate::{error::FenError, piece, Color, Error, Piece, Position};
+use crate::{bitboard::Bitboard, error::FenError, piece, Color, Error, Piece, Position};
 
 use super::Board;
 
 impl Board {
+    /// Parses a position in Forsyth-Edwards Notation. Besides the syntax, the
+    /// position itself is validated, e.g. that each player has exactly one
+    /// king, that castling rights and the en passant square are possible and
+    /// that the player who is not to move is not in check.
     pub fn from_fen(fen: &str) -> Result<Self, Error> {
+        let err = Error::FenError;
         let mut fen = fen.split_ascii_whitespace();
 
-        let mut found_white_king = false;
-        let mut found_black_king = false;
-
-        let mut tiles = [[None; 8]; 8];
-
-        let mut file = 0;
-        let mut rank = 0;
-
-        let tiles_part = fen.next().ok_or(Error::FenError(FenError::Pieces))?;
-        for c in tiles_part.chars() {
-            match c {
-                '/' => {
-                    rank += 1;
-                    file = 0;
-                }
-                '1'..='8' => {
-                    file += c as usize - '0' as usize;
-                }
-                _ => {
-                    let piece = Piece::from_name(c)?;
-                    if piece.kind == piece::Kind::King {
-                        *match piece.color {
-                            Color::White => &mut found_white_king,
-                            Color::Black => &mut found_black_king,
-                        } = true;
-                    }
-                    tiles[rank][file] = Some(piece);
-                    file += 1;
-                }
-            }
-        }
+        let tiles_part = fen.next().ok_or(err(FenError::Pieces))?;
+        let tiles = parse_tiles(tiles_part)?;
 
         let mut board = Board {
-            tiles,
+            tiles: [[None; 8]; 8],
             next_to_move: Color::White,
             can_castle_white_kingside: false,
             can_castle_white_queenside: false,
@@ -50,53 +26,95 @@ impl Board {
             en_passant_square: None,
             halfmove_counter: 0,
             move_number: 0,
+            pieces: [Bitboard::EMPTY; 6],
+            colors: [Bitboard::EMPTY; 2],
+            zobrist_key: 0,
         };
+        for (rank, row) in tiles.iter().enumerate() {
+            for (file, &piece) in row.iter().enumerate() {
+                board.set_tile(Position::new_unchecked(file as u8, rank as u8), piece);
+            }
+        }
+        validate_pieces(&board)?;
 
-        let next_to_move_part = fen.next().ok_or(Error::FenError(FenError::NextToMove))?;
+        let next_to_move_part = fen.next().ok_or(err(FenError::NextToMove))?;
         board.next_to_move = match next_to_move_part {
             "w" => Color::White,
             "b" => Color::Black,
-            _ => return Err(Error::FenError(FenError::NextToMove)),
+            _ => return Err(err(FenError::NextToMove)),
         };
 
-        let castling_part = fen.next().ok_or(Error::FenError(FenError::Castling))?;
-        for c in castling_part.chars() {
-            match c {
-                'K' => board.can_castle_white_kingside = true,
-                'Q' => board.can_castle_white_queenside = true,
-                'k' => board.can_castle_black_kingside = true,
-                'q' => board.can_castle_black_queenside = true,
-                '-' => {}
-                _ => return Err(Error::FenError(FenError::Castling)),
+        let castling_part = fen.next().ok_or(err(FenError::Castling))?;
+        if castling_part != "-" {
+            for c in castling_part.chars() {
+                let (color, rook_file, right) = match c {
+                    'K' => (Color::White, 7, &mut board.can_castle_white_kingside),
+                    'Q' => (Color::White, 0, &mut board.can_castle_white_queenside),
+                    'k' => (Color::Black, 7, &mut board.can_castle_black_kingside),
+                    'q' => (Color::Black, 0, &mut board.can_castle_black_queenside),
+                    _ => return Err(err(FenError::Castling)),
+                };
+                if *right {
+                    return Err(err(FenError::Castling));
+                }
+                *right = true;
+
+                let back_rank = match color {
+                    Color::White => 7,
+                    Color::Black => 0,
+                };
+                let king = Position::new_unchecked(4, back_rank);
+                let rook = Position::new_unchecked(rook_file, back_rank);
+                if board[king] != Some(Piece::new(color, piece::Kind::King))
+                    || board[rook] != Some(Piece::new(color, piece::Kind::Rook))
+                {
+                    return Err(err(FenError::CastlingRight(c)));
+                }
             }
         }
 
-        let en_passant_square_part = fen.next().ok_or(Error::FenError(FenError::EnPassant))?;
+        let en_passant_square_part = fen.next().ok_or(err(FenError::EnPassant))?;
         board.en_passant_square = match en_passant_square_part {
             "-" => None,
-            ep => match Position::from_str(ep) {
-                Ok(p) => Some(p),
-                Err(err) => return Err(err),
-            },
+            ep => {
+                let eps = Position::from_str(ep).map_err(|_| err(FenError::EnPassant))?;
+                validate_en_passant_square(&board, eps)?;
+                Some(eps)
+            }
         };
 
-        let halfmove_counter_part = fen
-            .next()
-            .ok_or(Error::FenError(FenError::HalfmoveCounter))?;
+        let halfmove_counter_part = fen.next().ok_or(err(FenError::HalfmoveCounter))?;
         board.halfmove_counter = halfmove_counter_part
             .parse()
-            .map_err(|_| Error::FenError(FenError::HalfmoveCounter))?;
+            .map_err(|_| err(FenError::HalfmoveCounter))?;
+        // The double pawn push that leaves the en passant square resets the
+        // halfmove counter
+        if let Some(eps) = board
+            .en_passant_square
+            .filter(|_| board.halfmove_counter != 0)
+        {
+            return Err(err(FenError::EnPassantSquare(eps)));
+        }
 
-        let move_number_part = fen.next().ok_or(Error::FenError(FenError::MoveNumber))?;
-        board.move_number = move_number_part
-            .parse()
-            .map_err(|_| Error::FenError(FenError::MoveNumber))?;
+        let move_number_part = fen.next().ok_or(err(FenError::MoveNumber))?;
+        board.move_number = match move_number_part.parse() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(err(FenError::MoveNumber)),
+        };
 
-        // TODO: Return error if game state is invalid
-        if !found_white_king || !found_black_king {
-            return Err(Error::InvalidGameState);
+        if fen.next().is_some() {
+            return Err(err(FenError::TrailingCharacters));
         }
 
+        let opponent = board.next_to_move.other();
+        let opponent_king = board.get_king_position(opponent);
+        let attackers = board.attackers(opponent_king, board.next_to_move, board.occupied());
+        if !attackers.is_empty() {
+            return Err(err(FenError::OpponentInCheck));
+        }
+
+        board.zobrist_key = board.zobrist();
+
         Ok(board)
     }
     pub fn to_fen(&self) -> String {
@@ -115,10 +133,10 @@ impl Board {
                     skip += 1;
                 }
             }
+            if skip != 0 {
+                fen.push_str(&format!("{}", skip));
+            }
             if rank != 7 {
-                if skip != 0 {
-                    fen.push_str(&format!("{}", skip));
-                }
                 fen.push('/');
             }
         }
@@ -157,7 +175,7 @@ impl Board {
         if let Some(ep) = self.en_passant_square {
             fen.push_str(&ep.to_string());
         } else {
-            fen.push_str("-");
+            fen.push('-');
         }
 
         fen.push(' ');
@@ -171,3 +189,105 @@ impl Board {
         fen
     }
 }
+
+/// Parses the piece placement part of a FEN string
+fn parse_tiles(tiles_part: &str) -> Result<[[Option<Piece>; 8]; 8], Error> {
+    let err = Error::FenError;
+    let mut tiles = [[None; 8]; 8];
+
+    let ranks = tiles_part.split('/').collect::<Vec<_>>();
+    if ranks.len() != 8 {
+        return Err(err(FenError::RankCount(ranks.len())));
+    }
+    for (rank, rank_part) in ranks.iter().enumerate() {
+        let mut file = 0;
+        let mut last_was_digit = false;
+        for (column, c) in rank_part.chars().enumerate() {
+            if file >= 8 {
+                return Err(err(FenError::RankTooLong { rank, column }));
+            }
+            match c {
+                '1'..='8' if !last_was_digit => {
+                    file += c as usize - '0' as usize;
+                    if file > 8 {
+                        return Err(err(FenError::RankTooLong { rank, column }));
+                    }
+                    last_was_digit = true;
+                }
+                _ => {
+                    let piece = Piece::from_name(c)
+                        .map_err(|_| err(FenError::InvalidCharacter { rank, column }))?;
+                    tiles[rank][file] = Some(piece);
+                    file += 1;
+                    last_was_digit = false;
+                }
+            }
+        }
+        if file < 8 {
+            return Err(err(FenError::RankTooShort { rank }));
+        }
+    }
+
+    Ok(tiles)
+}
+
+/// Checks that the number of pieces of each player is possible, and that no
+/// pawns are on the first or last rank
+fn validate_pieces(board: &Board) -> Result<(), Error> {
+    for &color in &[Color::White, Color::Black] {
+        let pieces = || {
+            board
+                .tiles
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|p| p.color == color)
+        };
+        let count = |kind| pieces().filter(|p| p.kind == kind).count();
+        let error = match count(piece::Kind::King) {
+            0 => Some(FenError::MissingKing(color)),
+            1 if count(piece::Kind::Pawn) > 8 => Some(FenError::TooManyPawns(color)),
+            1 if pieces().count() > 16 => Some(FenError::TooManyPieces(color)),
+            1 => None,
+            _ => Some(FenError::TooManyKings(color)),
+        };
+        if let Some(error) = error {
+            return Err(Error::FenError(error));
+        }
+    }
+
+    for &rank in &[0, 7] {
+        for file in 0..8 {
+            let pos = Position::new_unchecked(file, rank);
+            if board[pos].is_some_and(|p| p.kind == piece::Kind::Pawn) {
+                return Err(Error::FenError(FenError::PawnOnBackRank(pos)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `eps` could be the en passant square after the last move, i.e.
+/// that the opponent just moved a pawn two ranks over it
+fn validate_en_passant_square(board: &Board, eps: Position) -> Result<(), Error> {
+    let opponent = board.next_to_move.other();
+    let rank = eps.rank() as i8;
+    let expected_rank = match opponent {
+        Color::White => 5,
+        Color::Black => 2,
+    };
+    let pawn_pos = Position::new_i8(eps.file() as i8, rank + opponent.forwards());
+    let origin = Position::new_i8(eps.file() as i8, rank + opponent.backwards());
+    match (pawn_pos, origin) {
+        (Some(pawn_pos), Some(origin))
+            if rank == expected_rank
+                && board[eps].is_none()
+                && board[origin].is_none()
+                && board[pawn_pos] == Some(Piece::new(opponent, piece::Kind::Pawn)) =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::FenError(FenError::EnPassantSquare(eps))),
+    }
+}
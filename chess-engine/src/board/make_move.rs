@@ -0,0 +1,226 @@
+use crate::{piece, Color, Error, Move, Piece, Position};
+
+use super::Board;
+
+/// A move that has been made on a `Board`, together with everything needed to
+/// take it back again with `Board::unmake_move`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveRecord {
+    move_: Move,
+    piece: Piece,
+    captured: Option<Piece>,
+    castling: [bool; 4],
+    en_passant_square: Option<Position>,
+    halfmove_counter: u16,
+    zobrist_key: u64,
+}
+
+impl MoveRecord {
+    pub fn move_(&self) -> Move {
+        self.move_
+    }
+    /// The piece that was moved, as it was before the move (i.e. a pawn for
+    /// promotions)
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+    /// The piece that was captured by the move, if any. Also set for en
+    /// passant captures
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+    /// Whether `color` could castle on the kingside before the move was made
+    pub fn could_castle_kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.castling[0],
+            Color::Black => self.castling[2],
+        }
+    }
+    /// Whether `color` could castle on the queenside before the move was made
+    pub fn could_castle_queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.castling[1],
+            Color::Black => self.castling[3],
+        }
+    }
+    /// The en passant square before the move was made
+    pub fn en_passant_square(&self) -> Option<Position> {
+        self.en_passant_square
+    }
+    /// The halfmove counter before the move was made
+    pub fn halfmove_counter(&self) -> u16 {
+        self.halfmove_counter
+    }
+}
+
+impl Board {
+    /// Makes the move `move_` on the board, if it is legal. Promotions must be
+    /// given as for `Game::make_move`. Returns a record that can be passed to
+    /// `unmake_move` to restore the board.
+    ///
+    /// Unlike `Game::make_move` this doesn't check whether the game has ended,
+    /// so it can be used to search through positions without cloning.
+    ///
+    /// ```rust
+    /// # use chess_engine::{Board, Move};
+    /// let mut board = Board::default();
+    /// let record = board.make_move(Move::arabic("e2e4").unwrap()).unwrap();
+    /// board.unmake_move(&record);
+    /// assert_eq!(board, Board::default());
+    /// ```
+    pub fn make_move<M>(&mut self, move_: M) -> Result<MoveRecord, Error>
+    where
+        M: Into<Move>,
+    {
+        let move_ = move_.into();
+        self.validate_move(move_)?;
+        Ok(self.make_move_unchecked(move_))
+    }
+    /// Returns the error `make_move` would return for `move_`, if any
+    pub(crate) fn validate_move(&self, move_: Move) -> Result<(), Error> {
+        let piece = self[move_.from].ok_or(Error::NoPieceToMove)?;
+        if piece.color != self.next_to_move() {
+            return Err(Error::OtherPlayersTurn);
+        }
+        if !piece.moves(self, move_.from).any(|p| p == move_.to) {
+            return Err(Error::IllegalMove);
+        }
+        let is_promotion =
+            piece.kind == piece::Kind::Pawn && (move_.to.rank() == 0 || move_.to.rank() == 7);
+        match move_.promotion {
+            None if is_promotion => Err(Error::MissingPromotion),
+            Some(kind) if !is_promotion || !piece::Kind::PROMOTIONS.contains(&kind) => {
+                Err(Error::InvalidPromotion)
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Makes the move without checking if the piece at `move_.from` exists or
+    /// can move to `move_.to` legally, e.g. for moves from `legal_moves`.
+    /// Returns a record that can be passed to `unmake_move` to restore the
+    /// board.
+    ///
+    /// # Panics
+    /// If there is no piece at `move_.from`, or if `move_` is a promotion
+    /// without a promotion kind.
+    pub fn make_move_unchecked(&mut self, move_: Move) -> MoveRecord {
+        let piece = self[move_.from].unwrap();
+        let current_color = self.next_to_move();
+        let mut record = MoveRecord {
+            move_,
+            piece,
+            captured: self[move_.to],
+            castling: self.castling(),
+            en_passant_square: self.en_passant_square,
+            halfmove_counter: self.halfmove_counter,
+            zobrist_key: self.zobrist_key,
+        };
+
+        self.set_tile(move_.from, None);
+        // Handle promotion
+        if piece.kind == piece::Kind::Pawn && (move_.to.rank() == 7 || move_.to.rank() == 0) {
+            let promoted = Piece::new(current_color, move_.promotion.unwrap());
+            self.set_tile(move_.to, Some(promoted));
+        } else {
+            self.set_tile(move_.to, Some(piece));
+        }
+
+        // Handle castling
+        let delta_file = move_.to.file() as i8 - move_.from.file() as i8;
+        if piece.kind == piece::Kind::King && delta_file.abs() == 2 {
+            let (rook_pos, rook_dst) = castling_rook_move(move_);
+            let rook = self.set_tile(rook_pos, None);
+            self.set_tile(rook_dst, rook);
+        }
+
+        // Handle castling marking. A rook that moves from or is captured on its
+        // starting square can no longer castle
+        if piece.kind == piece::Kind::King {
+            self.cannot_castle_kingside(current_color);
+            self.cannot_castle_queenside(current_color);
+        }
+        for &pos in &[move_.from, move_.to] {
+            match (pos.file(), pos.rank()) {
+                (0, 7) => self.cannot_castle_queenside(Color::White),
+                (7, 7) => self.cannot_castle_kingside(Color::White),
+                (0, 0) => self.cannot_castle_queenside(Color::Black),
+                (7, 0) => self.cannot_castle_kingside(Color::Black),
+                _ => {}
+            }
+        }
+
+        // Handle en passant capture
+        if piece.kind == piece::Kind::Pawn && Some(move_.to) == self.en_passant_square() {
+            record.captured = self.set_tile(en_passant_target(move_, current_color), None);
+        }
+
+        // Handle en passant marking
+        let delta_rank = move_.to.rank() as i8 - move_.from.rank() as i8;
+        if piece.kind == piece::Kind::Pawn && delta_rank.abs() == 2 {
+            let eps_rank = move_.to.rank() as i8 + current_color.backwards();
+            self.set_en_passant_square(Some(Position::new_unchecked(
+                move_.to.file(),
+                eps_rank as u8,
+            )));
+        } else {
+            self.set_en_passant_square(None);
+        }
+
+        self.switch_next_to_move();
+        if record.captured.is_some() || piece.kind == piece::Kind::Pawn {
+            self.reset_halfmove_counter();
+        }
+
+        record
+    }
+    /// Takes back the move described by `record`, which must be the last move
+    /// made on the board and not yet taken back. The board is then equal to
+    /// what it was before the move.
+    pub fn unmake_move(&mut self, record: &MoveRecord) {
+        let move_ = record.move_;
+        let color = self.next_to_move.other();
+
+        self.next_to_move = color;
+        if color == Color::Black {
+            self.move_number -= 1;
+        }
+        self.halfmove_counter = record.halfmove_counter;
+        self.en_passant_square = record.en_passant_square;
+        self.can_castle_white_kingside = record.castling[0];
+        self.can_castle_white_queenside = record.castling[1];
+        self.can_castle_black_kingside = record.castling[2];
+        self.can_castle_black_queenside = record.castling[3];
+
+        self.set_tile(move_.to, None);
+        self.set_tile(move_.from, Some(record.piece));
+
+        let delta_file = move_.to.file() as i8 - move_.from.file() as i8;
+        if record.piece.kind == piece::Kind::King && delta_file.abs() == 2 {
+            let (rook_pos, rook_dst) = castling_rook_move(move_);
+            let rook = self.set_tile(rook_dst, None);
+            self.set_tile(rook_pos, rook);
+        }
+
+        if record.piece.kind == piece::Kind::Pawn && Some(move_.to) == record.en_passant_square {
+            self.set_tile(en_passant_target(move_, color), record.captured);
+        } else {
+            self.set_tile(move_.to, record.captured);
+        }
+        self.zobrist_key = record.zobrist_key;
+    }
+}
+
+/// Returns where the rook moves from and to when the king castles with `move_`
+fn castling_rook_move(move_: Move) -> (Position, Position) {
+    let delta_file = move_.to.file() as i8 - move_.from.file() as i8;
+    let rook_pos = Position::new_unchecked(if delta_file > 0 { 7 } else { 0 }, move_.to.rank());
+    let rook_dst_file = move_.to.file() as i8 + -delta_file / 2;
+    let rook_dst = Position::new_unchecked(rook_dst_file as u8, move_.to.rank());
+    (rook_pos, rook_dst)
+}
+
+/// Returns the position of the pawn captured by the en passant move `move_`
+fn en_passant_target(move_: Move, color: Color) -> Position {
+    let target_rank = move_.to.rank() as i8 + color.backwards();
+    Position::new_unchecked(move_.to.file(), target_rank as u8)
+}
@@ -0,0 +1,207 @@
+use crate::{
+    bitboard::{self, Bitboard},
+    piece::Kind,
+    Color, Move, Piece, Position,
+};
+
+use super::Board;
+
+/// Generates the legal moves of one player. Which pieces give check and which
+/// pieces are pinned to the king is computed once per position, so that moves
+/// never have to be tested by making them.
+pub(crate) struct MoveGen<'b> {
+    board: &'b Board,
+    color: Color,
+    king: Position,
+    checkers: Bitboard,
+    /// Pieces of `color` that stand between their king and an attacking rook,
+    /// bishop or queen, and so can only move along that line
+    pinned: Bitboard,
+    /// The squares pieces other than the king can move to. If in check, the
+    /// checking piece and the squares between it and the king.
+    evasions: Bitboard,
+}
+
+impl<'b> MoveGen<'b> {
+    pub fn new(board: &'b Board, color: Color) -> Self {
+        let king = board.get_king_position(color);
+        let them = color.other();
+        let occupied = board.occupied();
+        let checkers = board.attackers(king, them, occupied);
+
+        // Sliders that would attack the king if it weren't for pieces of
+        // `color` in between
+        let queens = board.bitboard(them, Kind::Queen);
+        let their_pieces = board.color_bitboard(them);
+        let snipers = bitboard::rook_attacks(king, their_pieces)
+            & (board.bitboard(them, Kind::Rook) | queens)
+            | bitboard::bishop_attacks(king, their_pieces)
+                & (board.bitboard(them, Kind::Bishop) | queens);
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = bitboard::between(king, sniper) & occupied;
+            if blockers.count() == 1 {
+                pinned |= blockers & board.color_bitboard(color);
+            }
+        }
+
+        let evasions = match checkers.first() {
+            None => !Bitboard::EMPTY,
+            Some(checker) if checkers.count() == 1 => checkers | bitboard::between(king, checker),
+            // Only the king can get out of a double check
+            Some(_) => Bitboard::EMPTY,
+        };
+
+        Self {
+            board,
+            color,
+            king,
+            checkers,
+            pinned,
+            evasions,
+        }
+    }
+    /// Returns the squares `piece`, standing on `from`, can legally move to
+    pub fn targets(&self, piece: Piece, from: Position) -> Bitboard {
+        let occupied = self.board.occupied();
+        let own = self.board.color_bitboard(self.color);
+        let attacks = match piece.kind {
+            Kind::King => return self.king_targets(),
+            Kind::Pawn => return self.pawn_targets(from),
+            Kind::Knight => bitboard::knight_attacks(from),
+            Kind::Bishop => bitboard::bishop_attacks(from, occupied),
+            Kind::Rook => bitboard::rook_attacks(from, occupied),
+            Kind::Queen => bitboard::queen_attacks(from, occupied),
+        };
+        attacks & !own & self.evasions & self.pin_line(from)
+    }
+    /// Returns the squares `piece`, standing on `from`, can legally move to
+    /// with a capture or a promotion
+    pub fn capture_targets(&self, piece: Piece, from: Position) -> Bitboard {
+        let mut captures = self.board.color_bitboard(self.color.other());
+        if piece.kind == Kind::Pawn {
+            captures |= match self.color {
+                Color::White => Bitboard(0xFF),
+                Color::Black => Bitboard(0xFF << 56),
+            };
+            if let Some(eps) = self.board.en_passant_square() {
+                captures |= eps.into();
+            }
+        }
+        self.targets(piece, from) & captures
+    }
+    /// Appends every legal move to `moves`. Moves that lead to a pawn
+    /// promotion are included once for every kind in `Kind::PROMOTIONS`.
+    pub fn legal_moves(&self, moves: &mut Vec<Move>) {
+        self.push_moves(moves, Self::targets);
+    }
+    /// Appends every legal capture and promotion to `moves`, like
+    /// `legal_moves`
+    pub fn captures(&self, moves: &mut Vec<Move>) {
+        self.push_moves(moves, Self::capture_targets);
+    }
+    fn push_moves(&self, moves: &mut Vec<Move>, targets: fn(&Self, Piece, Position) -> Bitboard) {
+        for from in self.board.color_bitboard(self.color) {
+            let piece = self.board[from].unwrap();
+            for to in targets(self, piece, from) {
+                if piece.kind == Kind::Pawn && (to.rank() == 0 || to.rank() == 7) {
+                    moves.extend(Kind::PROMOTIONS.iter().map(|&kind| Move {
+                        from,
+                        to,
+                        promotion: Some(kind),
+                    }));
+                } else {
+                    moves.push(Move::new(from, to));
+                }
+            }
+        }
+    }
+    /// The squares a piece on `from` can move to without exposing the king
+    fn pin_line(&self, from: Position) -> Bitboard {
+        if self.pinned.contains(from) {
+            bitboard::line(self.king, from)
+        } else {
+            !Bitboard::EMPTY
+        }
+    }
+    fn king_targets(&self) -> Bitboard {
+        let them = self.color.other();
+        // The king can not hide from a slider behind itself
+        let occupied = self.board.occupied() & !Bitboard::from(self.king);
+        let is_safe = |pos| self.board.attackers(pos, them, occupied).is_empty();
+
+        let mut targets = Bitboard::EMPTY;
+        for pos in bitboard::king_attacks(self.king) & !self.board.color_bitboard(self.color) {
+            if is_safe(pos) {
+                targets |= pos.into();
+            }
+        }
+
+        if !self.checkers.is_empty() {
+            return targets;
+        }
+        let rank = self.king.rank();
+        let castlings = [
+            (self.board.can_castle_kingside(self.color), 7, [5, 6]),
+            (self.board.can_castle_queenside(self.color), 0, [3, 2]),
+        ];
+        for &(can_castle, rook_file, path) in &castlings {
+            let rook = Position::new_unchecked(rook_file, rank);
+            let path = path.map(|file| Position::new_unchecked(file, rank));
+            if can_castle
+                && (bitboard::between(self.king, rook) & occupied).is_empty()
+                && path.iter().all(|&pos| is_safe(pos))
+            {
+                targets |= path[1].into();
+            }
+        }
+        targets
+    }
+    fn pawn_targets(&self, from: Position) -> Bitboard {
+        let occupied = self.board.occupied();
+        let forwards = self.color.forwards();
+
+        let mut targets = Bitboard::EMPTY;
+        if let Some(one_step) = Position::new_i8(from.file() as i8, from.rank() as i8 + forwards) {
+            if !occupied.contains(one_step) {
+                targets |= one_step.into();
+                let start_rank = match self.color {
+                    Color::White => 6,
+                    Color::Black => 1,
+                };
+                let two_steps =
+                    Position::new_i8_unchecked(from.file() as i8, from.rank() as i8 + 2 * forwards);
+                if from.rank() == start_rank && !occupied.contains(two_steps) {
+                    targets |= two_steps.into();
+                }
+            }
+        }
+        let attacks = bitboard::pawn_attacks(self.color, from);
+        targets |= attacks & self.board.color_bitboard(self.color.other());
+        targets &= self.evasions & self.pin_line(from);
+
+        match self.board.en_passant_square() {
+            Some(eps)
+                if self.color == self.board.next_to_move()
+                    && attacks.contains(eps)
+                    && self.is_legal_en_passant(from, eps) =>
+            {
+                targets | eps.into()
+            }
+            _ => targets,
+        }
+    }
+    /// Whether capturing en passant on `eps` from `from` leaves the king safe.
+    /// Two pawns leave the rank they were on, which can expose the king to a
+    /// rook or queen on that rank, so the capture is tested on the resulting
+    /// occupancy instead of using the pins.
+    fn is_legal_en_passant(&self, from: Position, eps: Position) -> bool {
+        let captured = Position::new_unchecked(eps.file(), from.rank());
+        let occupied =
+            self.board.occupied() & !Bitboard::from(from) & !Bitboard::from(captured) | eps.into();
+        let attackers = self
+            .board
+            .attackers(self.king, self.color.other(), occupied);
+        (attackers & !Bitboard::from(captured)).is_empty()
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{bitboard::Bitboard, piece::Kind, Move, Position};
+
+use super::Board;
+
+/// The kinds of pieces, from the least valuable
+const BY_VALUE: [Kind; 6] = [
+    Kind::Pawn,
+    Kind::Knight,
+    Kind::Bishop,
+    Kind::Rook,
+    Kind::Queen,
+    Kind::King,
+];
+
+impl Board {
+    /// Static exchange evaluation: returns how much material, in centipawns
+    /// by `Kind::value`, the player making `move_` wins if both players keep
+    /// capturing on `move_.to` with their least valuable piece, as long as it
+    /// pays off. Pieces behind others that attack the square, e.g. a rook
+    /// behind a queen, join in once the pieces in front of them have captured.
+    ///
+    /// Pins are not taken into account. Negative values mean that the move
+    /// loses material, e.g. a queen taking a defended pawn.
+    ///
+    /// ```rust
+    /// # use chess_engine::{Board, Move};
+    /// let board = Board::from_fen("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1").unwrap();
+    /// assert_eq!(board.see(Move::arabic("e1e5").unwrap()), 100 - 900);
+    /// ```
+    ///
+    /// # Panics
+    /// If there is no piece at `move_.from`
+    pub fn see(&self, move_: Move) -> i32 {
+        let piece = self[move_.from].unwrap();
+        let to = move_.to;
+        let mut occupied = self.occupied() & !Bitboard::from(move_.from);
+
+        let captured = match self[to] {
+            Some(captured) => captured.kind.value(),
+            None if piece.kind == Kind::Pawn && Some(to) == self.en_passant_square() => {
+                let target = Position::new_unchecked(to.file(), move_.from.rank());
+                occupied &= !Bitboard::from(target);
+                Kind::Pawn.value()
+            }
+            None => 0,
+        };
+        let (mut on_square, promotion) = match move_.promotion {
+            Some(kind) => (kind.value(), kind.value() - Kind::Pawn.value()),
+            None => (piece.kind.value(), 0),
+        };
+
+        // What each capture wins for the player making it, if the exchange
+        // stopped right after it
+        let mut gains = vec![captured + promotion];
+        let mut color = piece.color.other();
+        loop {
+            let attackers = self.attackers(to, color, occupied) & occupied;
+            let (from, kind) = match BY_VALUE.iter().find_map(|&kind| {
+                let pieces = attackers & self.bitboard(color, kind);
+                pieces.first().map(|pos| (pos, kind))
+            }) {
+                Some(attacker) => attacker,
+                None => break,
+            };
+            let promotes = kind == Kind::Pawn && (to.rank() == 0 || to.rank() == 7);
+            let promotion = if promotes {
+                Kind::Queen.value() - Kind::Pawn.value()
+            } else {
+                0
+            };
+            gains.push(on_square + promotion - gains.last().unwrap());
+            on_square = kind.value() + promotion;
+            occupied &= !Bitboard::from(from);
+            color = color.other();
+        }
+
+        // Each player may choose not to continue the exchange
+        for i in (1..gains.len()).rev() {
+            gains[i - 1] = -(-gains[i - 1]).max(gains[i]);
+        }
+        gains[0]
+    }
+}
@@ -1,8 +1,8 @@
-use crate::{piece, Board, Error, Game, GameState, Move};
+use crate::{Board, Error, Game, GameState, Move};
 
 /// # Example use:
 /// ```no_run
-/// use chess_engine::{piece, Board, Decider, Move};
+/// use chess_engine::{Board, Decider, Move};
 ///
 /// struct Application {
 ///     /* all data needed to run the application, such as windows, etc. */
@@ -13,10 +13,6 @@ use crate::{piece, Board, Error, Game, GameState, Move};
 ///         /* ... */
 ///         # panic!()
 ///     }
-///     fn get_pawn_promotion(&mut self) -> piece::Kind {
-///         /* ... */
-///         # panic!()
-///     }
 /// }
 ///
 /// fn main() {
@@ -24,18 +20,17 @@ use crate::{piece, Board, Error, Game, GameState, Move};
 ///     app.run(Board::default()).unwrap();
 /// }
 /// ```
-
 pub trait Decider {
+    /// Returns the move to make on `board`. Moves that promote a pawn must
+    /// carry the kind of piece to promote to in `Move::promotion`.
     fn get_move(&mut self, board: &Board) -> Move;
-    fn get_pawn_promotion(&mut self) -> piece::Kind;
 
     /// Runs a game of chess with on `board`. Do not overwrite
     fn run(&mut self, board: Board) -> Result<GameState, Error> {
         let mut game = Game::new(board);
         loop {
-            let game_state =
-                game.make_move(self.get_move(game.board()), || self.get_pawn_promotion())?;
-            if game_state != GameState::Ongoing {
+            let game_state = game.make_move(self.get_move(game.board()))?;
+            if !game_state.is_ongoing() {
                 break Ok(game_state);
             }
         }
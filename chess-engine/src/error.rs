@@ -1,25 +1,94 @@
 use std::error::Error as StdError;
 use std::fmt;
 
+use crate::{Color, Position};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     OtherPlayersTurn,
     NoPieceToMove,
     IllegalMove,
+    AmbiguousMove,
+    MissingPromotion,
+    InvalidPromotion,
+    GameOver,
+    CannotClaimDraw,
     UnknwonPiece(char),
     ParsingError,
     FenError(FenError),
+    PgnError(PgnError),
     InvalidGameState,
 }
 
+/// What is wrong with a FEN string. Where a position in the piece placement is
+/// given, `rank` is the index of the rank in the FEN (0 for the 8th rank) and
+/// `column` is the offset of the character within that rank.
 #[derive(Debug, PartialEq, Eq)]
 pub enum FenError {
     Pieces,
+    /// A character that is not a piece, a digit from 1 to 8 or '/', or a digit
+    /// directly following another digit
+    InvalidCharacter {
+        rank: usize,
+        column: usize,
+    },
+    /// The rank describes more than 8 files
+    RankTooLong {
+        rank: usize,
+        column: usize,
+    },
+    /// The rank describes less than 8 files
+    RankTooShort {
+        rank: usize,
+    },
+    /// The piece placement does not have exactly 8 ranks
+    RankCount(usize),
+    PawnOnBackRank(Position),
+    MissingKing(Color),
+    TooManyKings(Color),
+    TooManyPawns(Color),
+    /// More than 16 pieces, including pawns and the king
+    TooManyPieces(Color),
     NextToMove,
     Castling,
+    /// A castling right for which the king or the rook is not on its starting
+    /// square
+    CastlingRight(char),
     EnPassant,
+    /// An en passant square that could not have been left by the last move
+    EnPassantSquare(Position),
     HalfmoveCounter,
     MoveNumber,
+    /// Anything after the move number
+    TrailingCharacters,
+    /// The player who is not to move is in check
+    OpponentInCheck,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PgnError {
+    /// Reading the input failed
+    Io,
+    /// A tag pair is malformed
+    Tag {
+        line: usize,
+    },
+    /// A token in the movetext is not valid or not allowed where it is
+    Token {
+        line: usize,
+    },
+    /// A move is malformed, illegal or ambiguous
+    Move {
+        line: usize,
+        san: String,
+    },
+    /// The result token does not match how the game ended, or the `Result`
+    /// tag
+    Result {
+        line: usize,
+    },
+    UnterminatedComment,
+    UnterminatedVariation,
 }
 
 impl StdError for Error {
@@ -34,9 +103,15 @@ impl fmt::Display for Error {
             Self::OtherPlayersTurn => write!(f, "Other players turn"),
             Self::NoPieceToMove => write!(f, "No piece to move"),
             Self::IllegalMove => write!(f, "Illegal move"),
+            Self::AmbiguousMove => write!(f, "Ambiguous move"),
+            Self::MissingPromotion => write!(f, "Missing promotion piece"),
+            Self::InvalidPromotion => write!(f, "Invalid promotion piece"),
+            Self::GameOver => write!(f, "The game is over"),
+            Self::CannotClaimDraw => write!(f, "No draw can be claimed"),
             Self::UnknwonPiece(c) => write!(f, "Unknown piece {}", c),
             Self::ParsingError => write!(f, "Parsing error"),
-            Self::FenError(err) => write!(f, "Fen parsing error at {} part", err),
+            Self::FenError(err) => write!(f, "Invalid fen: {}", err),
+            Self::PgnError(err) => write!(f, "Pgn parsing error: {}", err),
             Self::InvalidGameState => write!(f, "Invalid game state"),
         }
     }
@@ -45,12 +120,43 @@ impl fmt::Display for Error {
 impl fmt::Display for FenError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Pieces => write!(f, "pieces"),
-            Self::NextToMove => write!(f, "next to move"),
-            Self::Castling => write!(f, "castling"),
-            Self::EnPassant => write!(f, "en passant"),
-            Self::HalfmoveCounter => write!(f, "halfmove counter"),
-            Self::MoveNumber => write!(f, "move number"),
+            Self::Pieces => write!(f, "missing piece placement"),
+            Self::InvalidCharacter { rank, column } => {
+                write!(f, "invalid character at rank {}, column {}", rank, column)
+            }
+            Self::RankTooLong { rank, column } => {
+                write!(f, "rank {} is too long at column {}", rank, column)
+            }
+            Self::RankTooShort { rank } => write!(f, "rank {} is too short", rank),
+            Self::RankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            Self::PawnOnBackRank(pos) => write!(f, "pawn on back rank at {}", pos),
+            Self::MissingKing(color) => write!(f, "missing {:?} king", color),
+            Self::TooManyKings(color) => write!(f, "more than one {:?} king", color),
+            Self::TooManyPawns(color) => write!(f, "more than 8 {:?} pawns", color),
+            Self::TooManyPieces(color) => write!(f, "more than 16 {:?} pieces", color),
+            Self::NextToMove => write!(f, "invalid next to move part"),
+            Self::Castling => write!(f, "invalid castling part"),
+            Self::CastlingRight(c) => write!(f, "impossible castling right {}", c),
+            Self::EnPassant => write!(f, "invalid en passant part"),
+            Self::EnPassantSquare(pos) => write!(f, "impossible en passant square {}", pos),
+            Self::HalfmoveCounter => write!(f, "invalid halfmove counter"),
+            Self::MoveNumber => write!(f, "invalid move number"),
+            Self::TrailingCharacters => write!(f, "unexpected characters after move number"),
+            Self::OpponentInCheck => write!(f, "the player not to move is in check"),
+        }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io => write!(f, "could not read input"),
+            Self::Tag { line } => write!(f, "malformed tag pair on line {}", line),
+            Self::Token { line } => write!(f, "unexpected token on line {}", line),
+            Self::Move { line, san } => write!(f, "invalid move {} on line {}", san, line),
+            Self::Result { line } => write!(f, "result on line {} does not match the game", line),
+            Self::UnterminatedComment => write!(f, "unterminated comment"),
+            Self::UnterminatedVariation => write!(f, "unterminated variation"),
         }
     }
 }
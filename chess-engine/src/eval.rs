@@ -0,0 +1,478 @@
+//! Static evaluation of positions, in centipawns.
+//!
+//! Every term is computed separately for the middlegame and the endgame, and
+//! the two are blended by how much material is left on the board (tapered
+//! evaluation).
+//!
+//! # Example use:
+//! ```rust
+//! # use chess_engine::{eval, Board};
+//! assert_eq!(eval::evaluate(&Board::default()), 0);
+//!
+//! // White is a queen up, but it's black's turn
+//! let board = Board::from_fen("3qk3/8/8/8/8/8/8/3QK2Q b - - 0 1").unwrap();
+//! let breakdown = eval::breakdown(&board);
+//! assert!(breakdown.material < -800);
+//! assert_eq!(breakdown.total(), eval::evaluate(&board));
+//! ```
+
+use std::{fmt, ops};
+
+use crate::{
+    bitboard::{self, Bitboard},
+    piece::Kind,
+    Board, Color, Position,
+};
+
+/// The score of a position split up by the terms it is made of, in
+/// centipawns from the view of the player to move
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Breakdown {
+    pub material: i32,
+    /// Bonuses and penalties for where each piece stands
+    pub piece_squares: i32,
+    /// Penalties for doubled and isolated pawns, and bonuses for passed pawns
+    pub pawn_structure: i32,
+    /// Bonuses for pawns in front of the king, and penalties for open files
+    /// next to it and for pieces attacking the squares around it
+    pub king_safety: i32,
+    /// Bonuses for the number of squares the pieces can move to
+    pub mobility: i32,
+    /// How much material is left, from 0 (only kings and pawns) to 24 (all
+    /// pieces). The terms are blended from their endgame values at 0 to their
+    /// middlegame values at 24.
+    pub phase: i32,
+}
+
+impl Breakdown {
+    /// Returns the sum of all terms, i.e. what `evaluate` returns
+    pub fn total(&self) -> i32 {
+        self.material + self.piece_squares + self.pawn_structure + self.king_safety + self.mobility
+    }
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Material:       {:>6}", self.material)?;
+        writeln!(f, "Piece squares:  {:>6}", self.piece_squares)?;
+        writeln!(f, "Pawn structure: {:>6}", self.pawn_structure)?;
+        writeln!(f, "King safety:    {:>6}", self.king_safety)?;
+        writeln!(f, "Mobility:       {:>6}", self.mobility)?;
+        writeln!(f, "Total:          {:>6}", self.total())?;
+        write!(f, "Phase:          {:>6}", self.phase)
+    }
+}
+
+/// Returns the score of `board` in centipawns, from the view of the player to
+/// move. Positive scores mean that the player to move is better.
+pub fn evaluate(board: &Board) -> i32 {
+    breakdown(board).total()
+}
+
+/// Returns the score of `board` split up by term. See `evaluate`.
+pub fn breakdown(board: &Board) -> Breakdown {
+    let phase = board
+        .pieces
+        .iter()
+        .zip(&PHASE)
+        .map(|(pieces, phase)| pieces.count() as i32 * phase)
+        .sum::<i32>()
+        .min(MAX_PHASE);
+    let sign = match board.next_to_move() {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let term = |f: fn(&Board, Color) -> Score| {
+        let score = f(board, Color::White) - f(board, Color::Black);
+        sign * (score.mg * phase + score.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    };
+    Breakdown {
+        material: term(material),
+        piece_squares: term(piece_squares),
+        pawn_structure: term(pawn_structure),
+        king_safety: term(king_safety),
+        mobility: term(mobility),
+        phase,
+    }
+}
+
+fn material(board: &Board, color: Color) -> Score {
+    board
+        .pieces
+        .iter()
+        .zip(&MATERIAL)
+        .map(|(&pieces, &value)| value * (pieces & board.color_bitboard(color)).count() as i32)
+        .fold(Score::default(), |a, b| a + b)
+}
+
+fn piece_squares(board: &Board, color: Color) -> Score {
+    let mut score = Score::default();
+    for (kind, &pieces) in board.pieces.iter().enumerate() {
+        for pos in pieces & board.color_bitboard(color) {
+            // The tables are from white's view, with a8 first
+            let i = match color {
+                Color::White => bitboard::index(pos),
+                Color::Black => bitboard::index(pos) ^ 56,
+            };
+            score += Score::new(MG_TABLES[kind][i], EG_TABLES[kind][i]);
+        }
+    }
+    score
+}
+
+fn pawn_structure(board: &Board, color: Color) -> Score {
+    let pawns = board.bitboard(color, Kind::Pawn);
+    let their_pawns = board.bitboard(color.other(), Kind::Pawn);
+
+    let mut score = Score::default();
+    for file in 0..8 {
+        let count = (pawns & file_mask(file)).count() as i32;
+        if count > 1 {
+            score += DOUBLED_PAWN * (count - 1);
+        }
+    }
+    for pos in pawns {
+        if (pawns & adjacent_files(pos.file())).is_empty() {
+            score += ISOLATED_PAWN;
+        }
+        if (their_pawns & front_span(color, pos)).is_empty() {
+            score += PASSED_PAWN[relative_rank(color, pos)];
+        }
+    }
+    score
+}
+
+fn king_safety(board: &Board, color: Color) -> Score {
+    let king = board.get_king_position(color);
+    let pawns = board.bitboard(color, Kind::Pawn);
+
+    // Pawns up to two ranks in front of the king
+    let rank = king.rank() as i8;
+    let near_ranks = (1..=2)
+        .map(|distance| rank + distance * color.forwards())
+        .filter(|rank| (0..8).contains(rank))
+        .fold(0u64, |mask, rank| mask | 0xFF << (8 * rank));
+    let shield = front_span(color, king) & Bitboard(near_ranks);
+    let mut score = PAWN_SHIELD * (pawns & shield).count() as i32;
+
+    let files = king.file().saturating_sub(1)..=(king.file() + 1).min(7);
+    for file in files {
+        if (pawns & file_mask(file)).is_empty() {
+            score += OPEN_FILE_NEAR_KING;
+        }
+    }
+
+    let zone = bitboard::king_attacks(king) | king.into();
+    let occupied = board.occupied();
+    let them = color.other();
+    let mut units = 0;
+    for (kind, weight) in [
+        (Kind::Knight, 2),
+        (Kind::Bishop, 2),
+        (Kind::Rook, 3),
+        (Kind::Queen, 5),
+    ] {
+        for pos in board.bitboard(them, kind) {
+            units += weight * (attacks(kind, pos, occupied) & zone).count() as i32;
+        }
+    }
+    score + KING_ATTACK * units
+}
+
+fn mobility(board: &Board, color: Color) -> Score {
+    let occupied = board.occupied();
+    let them = color.other();
+    // Squares attacked by enemy pawns are not worth counting
+    let pawn_attacks = board
+        .bitboard(them, Kind::Pawn)
+        .fold(Bitboard::EMPTY, |attacks, pos| {
+            attacks | bitboard::pawn_attacks(them, pos)
+        });
+    let area = !board.color_bitboard(color) & !pawn_attacks;
+
+    let mut score = Score::default();
+    for &(kind, average, bonus) in &MOBILITY {
+        for pos in board.bitboard(color, kind) {
+            let squares = (attacks(kind, pos, occupied) & area).count() as i32;
+            score += bonus * (squares - average);
+        }
+    }
+    score
+}
+
+fn attacks(kind: Kind, pos: Position, occupied: Bitboard) -> Bitboard {
+    match kind {
+        Kind::Knight => bitboard::knight_attacks(pos),
+        Kind::Bishop => bitboard::bishop_attacks(pos, occupied),
+        Kind::Rook => bitboard::rook_attacks(pos, occupied),
+        Kind::Queen => bitboard::queen_attacks(pos, occupied),
+        Kind::King => bitboard::king_attacks(pos),
+        Kind::Pawn => unreachable!(),
+    }
+}
+
+/// How many ranks a pawn of `color` on `pos` has advanced, counting from the
+/// back rank
+fn relative_rank(color: Color, pos: Position) -> usize {
+    match color {
+        Color::White => 7 - pos.rank() as usize,
+        Color::Black => pos.rank() as usize,
+    }
+}
+
+fn file_mask(file: u8) -> Bitboard {
+    Bitboard(0x0101_0101_0101_0101 << file)
+}
+
+fn adjacent_files(file: u8) -> Bitboard {
+    let left = if file > 0 {
+        file_mask(file - 1)
+    } else {
+        Bitboard::EMPTY
+    };
+    let right = if file < 7 {
+        file_mask(file + 1)
+    } else {
+        Bitboard::EMPTY
+    };
+    left | right
+}
+
+/// The squares in front of `pos`, from the view of `color`, on its file and
+/// the files next to it
+fn front_span(color: Color, pos: Position) -> Bitboard {
+    let rank = pos.rank() as u32;
+    let ahead = match color {
+        // Towards the 8th rank, i.e. lower indices
+        Color::White => !(u64::MAX << (8 * rank)),
+        Color::Black => u64::MAX.checked_shl(8 * (rank + 1)).unwrap_or(0),
+    };
+    Bitboard(ahead) & (file_mask(pos.file()) | adjacent_files(pos.file()))
+}
+
+/// A score for the middlegame and one for the endgame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Score {
+    mg: i32,
+    eg: i32,
+}
+
+impl Score {
+    const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+}
+
+impl ops::Add for Score {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl ops::Sub for Score {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl ops::Mul<i32> for Score {
+    type Output = Self;
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl ops::AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+const MAX_PHASE: i32 = 24;
+
+// The tables below are indexed by `Kind as usize`, i.e. pawn, rook, knight,
+// bishop, queen, king
+
+/// How much each piece counts towards the phase
+const PHASE: [i32; 6] = [0, 2, 1, 1, 4, 0];
+
+const MATERIAL: [Score; 6] = [
+    Score::new(82, 94),
+    Score::new(477, 512),
+    Score::new(337, 281),
+    Score::new(365, 297),
+    Score::new(1025, 936),
+    Score::new(0, 0),
+];
+
+const DOUBLED_PAWN: Score = Score::new(-10, -20);
+const ISOLATED_PAWN: Score = Score::new(-10, -15);
+/// Indexed by how far the pawn has advanced, see `relative_rank`
+const PASSED_PAWN: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(0, 10),
+    Score::new(5, 15),
+    Score::new(10, 25),
+    Score::new(20, 45),
+    Score::new(35, 75),
+    Score::new(60, 120),
+    Score::new(0, 0),
+];
+
+const PAWN_SHIELD: Score = Score::new(12, 0);
+const OPEN_FILE_NEAR_KING: Score = Score::new(-20, 0);
+/// Per attack on the squares around the king, weighted by the attacker
+const KING_ATTACK: Score = Score::new(-6, -1);
+
+/// The kinds of pieces mobility is counted for, how many squares they can
+/// move to on average, and the bonus for each square more than that
+const MOBILITY: [(Kind, i32, Score); 4] = [
+    (Kind::Knight, 4, Score::new(4, 4)),
+    (Kind::Bishop, 6, Score::new(5, 5)),
+    (Kind::Rook, 7, Score::new(2, 4)),
+    (Kind::Queen, 13, Score::new(1, 2)),
+];
+
+/// Piece-square tables for the middlegame, from white's view with a8 first
+#[rustfmt::skip]
+const MG_TABLES: [[i32; 64]; 6] = [
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Rook
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    // Knight
+    [
+       -167, -89, -34, -49,  61, -97, -15, -107,
+        -73, -41,  72,  36,  23,  62,   7,  -17,
+        -47,  60,  37,  65,  84, 129,  73,   44,
+         -9,  17,  19,  53,  37,  69,  18,   22,
+        -13,   4,  16,  13,  28,  19,  21,   -8,
+        -23,  -9,  12,  10,  19,  17,  25,  -16,
+        -29, -53, -12,  -3,  -1,  18, -14,  -19,
+       -105, -21, -58, -33, -17, -28, -19,  -23,
+    ],
+    // Bishop
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    // Queen
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    // King
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+/// Piece-square tables for the endgame, from white's view with a8 first
+#[rustfmt::skip]
+const EG_TABLES: [[i32; 64]; 6] = [
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Rook
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    // Knight
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    // Bishop
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    // Queen
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    // King
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
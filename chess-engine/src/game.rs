@@ -1,17 +1,20 @@
-use crate::{piece, Board, Color, Error, Move, Piece, Position};
+use crate::{Board, Color, Error, Move, MoveRecord};
+
+mod state;
+
+pub use state::{DrawReason, GameState, WinReason};
 
 /// # Example use:
 /// ```rust
-/// # use chess_engine::{piece, Board, Game, GameState, Move};
+/// # use chess_engine::{Board, Game, GameState, Move};
 ///
 /// let mut game = Game::new(Board::default());
 /// loop {
-///     # fn get_move() -> Move { Move { from: (0, 0).into(), to: (0, 0).into() } }
-///     # fn get_promotion() -> piece::Kind { piece::Kind::Queen }
-///     match game.make_move(get_move(), || get_promotion()) {
-///         Ok(GameState::Ongoing) => {}
+///     # fn get_move() -> Move { Move::new((0, 0).into(), (0, 0).into()) }
+///     match game.make_move(get_move()) {
+///         Ok(s) if s.is_ongoing() => {}
 ///         Ok(s) => {
-///             println!("{:?}", s);
+///             println!("{} ({})", s, s.result());
 ///             break;
 ///         }
 ///         Err(err) => {
@@ -24,144 +27,202 @@ use crate::{piece, Board, Color, Error, Move, Piece, Position};
 #[derive(Debug, Clone)]
 pub struct Game {
     board: Board,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GameState {
-    Ongoing,
-    Checkmate { winner: Color },
-    Draw,
+    initial_board: Board,
+    history: Vec<MoveRecord>,
+    undone: Vec<Move>,
+    /// The key of every position that has occurred, including the current one
+    positions: Vec<u64>,
+    state: GameState,
+    /// Whether the game was ended other than by a move, e.g. by `resign`
+    ended: bool,
 }
 
 impl Game {
+    /// Starts a game from `board`. The game may already be over, e.g. if the
+    /// player to move is checkmated, see `state`.
     pub fn new(board: Board) -> Self {
-        Self { board }
+        let mut game = Self {
+            positions: vec![board.position_key()],
+            initial_board: board.clone(),
+            board,
+            history: Vec::new(),
+            undone: Vec::new(),
+            state: GameState::Ongoing,
+            ended: false,
+        };
+        game.state = game.compute_state();
+        game
     }
     pub fn board(&self) -> &Board {
         &self.board
     }
-    pub fn make_move<M, P>(&mut self, move_: M, pawn_promotion: P) -> Result<GameState, Error>
+    /// Returns the board the game started from, i.e. the one passed to `new`
+    pub fn initial_board(&self) -> &Board {
+        &self.initial_board
+    }
+    /// Returns the state of the game after the last move, or after it was
+    /// ended by e.g. `resign`. Before any moves are made, the state of the
+    /// board the game was started from.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+    /// Returns every legal move in the current position. See
+    /// `Board::legal_moves`.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.board.legal_moves()
+    }
+    /// Makes the move `move_`. Moves where a pawn reaches the last rank must
+    /// have `move_.promotion` set to one of `piece::Kind::PROMOTIONS`, all other
+    /// moves must have it set to `None`.
+    pub fn make_move<M>(&mut self, move_: M) -> Result<GameState, Error>
     where
         M: Into<Move>,
-        P: FnOnce() -> piece::Kind,
     {
         let move_ = move_.into();
-        if let Some(piece) = self.board[move_.from] {
-            if piece.color != self.board.next_to_move() {
-                return Err(Error::OtherPlayersTurn);
-            }
-            if !piece.moves(self.board(), move_.from).any(|p| p == move_.to) {
-                return Err(Error::IllegalMove);
-            }
-        } else {
-            return Err(Error::NoPieceToMove);
+        if !self.state.is_ongoing() {
+            return Err(Error::GameOver);
         }
-        self.make_move_unchecked(move_, pawn_promotion)
+        self.board.validate_move(move_)?;
+        self.undone.clear();
+        Ok(self.make_move_unchecked(move_))
     }
-    /// Make the move without checking if the piece at `move_.from` exists or
-    /// can move to `move_.to` legally.
+    /// Takes back the last move made. Returns the move that was taken back,
+    /// or `None` if no moves have been made.
     ///
-    /// `checks` signifies whether the opponent will be in check after the move.
-    fn make_move_unchecked<M, P>(&mut self, move_: M, pawn_promotion: P) -> Result<GameState, Error>
-    where
-        M: Into<Move>,
-        P: FnOnce() -> piece::Kind,
-    {
-        let move_: Move = move_.into();
-        let piece = self.board[move_.from].unwrap();
-        let current_color = self.board.next_to_move();
-        let mut captured = self.board[move_.to];
-
-        self.board[move_.to] = self.board[move_.from].take();
-
-        // Handle promotion
-        if piece.kind == piece::Kind::Pawn && (move_.to.rank() == 7 || move_.to.rank() == 0) {
-            let promoted = Piece::new(current_color, pawn_promotion());
-            self.board[move_.to] = Some(promoted);
-        }
-
-        // Handle castling
-        let delta_file = move_.to.file() as i8 - move_.from.file() as i8;
-        if piece.kind == piece::Kind::King && delta_file.abs() == 2 {
-            let rook_pos =
-                Position::new_unchecked(if delta_file > 0 { 7 } else { 0 }, move_.to.rank());
-            let rook_dst_file = move_.to.file() as i8 + -delta_file / 2;
-            let rook_dst = Position::new_unchecked(rook_dst_file as u8, move_.to.rank());
-            self.board[rook_dst] = self.board[rook_pos].take();
+    /// The move can be made again with `redo`. A game that ended by a move,
+    /// e.g. by checkmate, continues after the move is taken back, but one that
+    /// was ended otherwise, e.g. by `resign` or `claim_draw`, stays over and
+    /// `None` is returned.
+    pub fn undo(&mut self) -> Option<Move> {
+        if self.ended {
+            return None;
         }
-
-        // Handle castling marking
-        match (piece.kind, move_.from.file()) {
-            (piece::Kind::King, _) => {
-                self.board.cannot_castle_kingside(current_color);
-                self.board.cannot_castle_queenside(current_color);
-            }
-            (piece::Kind::Rook, 0) => self.board.cannot_castle_queenside(current_color),
-            (piece::Kind::Rook, 7) => self.board.cannot_castle_kingside(current_color),
-            _ => {}
+        let record = self.history.pop()?;
+        self.board.unmake_move(&record);
+        self.positions.pop();
+        self.state = self.compute_state();
+        self.undone.push(record.move_());
+        Some(record.move_())
+    }
+    /// Makes the last move taken back by `undo` again. Returns `None` if there
+    /// is nothing to redo or if the game is over. Making any other move clears
+    /// the moves that can be redone.
+    pub fn redo(&mut self) -> Option<GameState> {
+        if !self.state.is_ongoing() {
+            return None;
         }
-
-        // Handle en passant capture
-        if piece.kind == piece::Kind::Pawn && Some(move_.to) == self.board.en_passant_square() {
-            let target_rank = move_.to.rank() as i8 + current_color.backwards();
-            let target = Position::new_unchecked(move_.to.file(), target_rank as u8);
-            captured = self.board[target].take();
+        let move_ = self.undone.pop()?;
+        Some(self.make_move_unchecked(move_))
+    }
+    /// Returns all moves made so far, in the order they were made
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+    /// Ends the game in a draw if the player to move is allowed to claim one,
+    /// otherwise `Err(Error::CannotClaimDraw)` is returned.
+    pub fn claim_draw(&mut self) -> Result<GameState, Error> {
+        match self.state {
+            GameState::DrawClaimable(reason) => self.end(GameState::Draw(reason)),
+            _ => Err(Error::CannotClaimDraw),
         }
-
-        // Handle en passant marking
-        let delta_rank = move_.to.rank() as i8 - move_.from.rank() as i8;
-        if piece.kind == piece::Kind::Pawn && delta_rank.abs() == 2 {
-            let eps_rank = move_.to.rank() as i8 + current_color.backwards();
-            self.board
-                .set_en_passant_square(Some(Position::new_unchecked(
-                    move_.to.file(),
-                    eps_rank as u8,
-                )));
+    }
+    /// Ends the game with `color` resigning
+    pub fn resign(&mut self, color: Color) -> Result<GameState, Error> {
+        self.end(GameState::Win {
+            winner: color.other(),
+            reason: WinReason::Resignation,
+        })
+    }
+    /// Ends the game in a draw agreed upon by both players
+    pub fn agree_draw(&mut self) -> Result<GameState, Error> {
+        self.end(GameState::Draw(DrawReason::Agreement))
+    }
+    /// Ends the game with `color` running out of time. This is a loss for
+    /// `color`, unless the opponent has insufficient material to checkmate, in
+    /// which case it is a draw.
+    pub fn time_out(&mut self, color: Color) -> Result<GameState, Error> {
+        self.end(if self.board.has_insufficient_material(color.other()) {
+            GameState::Draw(DrawReason::Timeout)
         } else {
-            self.board.set_en_passant_square(None);
-        }
-
-        self.board.switch_next_to_move();
-        if captured.is_some() || piece.kind == piece::Kind::Pawn {
-            self.board.reset_halfmove_counter();
-        }
-
-        let mut has_moves = false;
-        'outer: for rank in 0..8 {
-            for file in 0..8 {
-                let pos = Position::new_unchecked(file, rank);
-                if let Some(piece) = self.board[pos] {
-                    if piece.color == self.board.next_to_move()
-                        && piece.moves(&self.board, pos).count() > 0
-                    {
-                        has_moves = true;
-                        break 'outer;
-                    }
-                }
+            GameState::Win {
+                winner: color.other(),
+                reason: WinReason::Timeout,
             }
+        })
+    }
+    /// Ends the game with the result decided outside of the game. `winner` is
+    /// `None` for a draw.
+    pub fn adjudicate(&mut self, winner: Option<Color>) -> Result<GameState, Error> {
+        self.end(match winner {
+            Some(winner) => GameState::Win {
+                winner,
+                reason: WinReason::Adjudication,
+            },
+            None => GameState::Draw(DrawReason::Adjudication),
+        })
+    }
+    fn end(&mut self, state: GameState) -> Result<GameState, Error> {
+        if !self.state.is_ongoing() {
+            return Err(Error::GameOver);
         }
-        if !has_moves {
-            if piece::util::threatened_at(
-                self.board.get_king_position(self.board.next_to_move()),
-                &[],
-                &[],
-                self.board.next_to_move(),
-                &self.board,
-            ) {
-                Ok(GameState::Checkmate {
+        self.state = state;
+        self.ended = true;
+        Ok(state)
+    }
+    /// Returns the `Board::position_key` of every position that has occurred,
+    /// including the current one
+    pub(crate) fn positions(&self) -> &[u64] {
+        &self.positions
+    }
+    /// Returns how many times the current position has occurred, including
+    /// now
+    pub fn repetitions(&self) -> usize {
+        let current = self.positions.last().unwrap();
+        // Positions before the last capture or pawn move can not be repeated
+        let since =
+            (self.positions.len() - 1).saturating_sub(self.board.halfmove_counter() as usize);
+        self.positions[since..]
+            .iter()
+            .filter(|&key| key == current)
+            .count()
+    }
+    /// Make the move without checking if the piece at `move_.from` exists or
+    /// can move to `move_.to` legally.
+    fn make_move_unchecked(&mut self, move_: Move) -> GameState {
+        let record = self.board.make_move_unchecked(move_);
+        self.history.push(record);
+        self.positions.push(self.board.position_key());
+        self.state = self.compute_state();
+        self.state
+    }
+    fn compute_state(&self) -> GameState {
+        if self.board.legal_moves().is_empty() {
+            return if self.board.is_check() {
+                GameState::Win {
                     winner: self.board.next_to_move().other(),
-                })
+                    reason: WinReason::Checkmate,
+                }
             } else {
-                Ok(GameState::Draw)
-            }
-        } else if self.board.halfmove_counter == 50 {
-            Ok(GameState::Draw)
+                GameState::Draw(DrawReason::Stalemate)
+            };
+        }
+        if self.board.has_insufficient_material(Color::White)
+            && self.board.has_insufficient_material(Color::Black)
+        {
+            return GameState::Draw(DrawReason::InsufficientMaterial);
+        }
+        let repetitions = self.repetitions();
+        let halfmoves = self.board.halfmove_counter();
+        if repetitions >= 5 {
+            GameState::Draw(DrawReason::FivefoldRepetition)
+        } else if halfmoves >= 150 {
+            GameState::Draw(DrawReason::SeventyFiveMoveRule)
+        } else if repetitions >= 3 {
+            GameState::DrawClaimable(DrawReason::ThreefoldRepetition)
+        } else if halfmoves >= 100 {
+            GameState::DrawClaimable(DrawReason::FiftyMoveRule)
         } else {
-            Ok(GameState::Ongoing)
+            GameState::Ongoing
         }
     }
-    
-    pub fn unsafe_set_piece(&mut self, pos: Position, piece: Piece) {
-        self.board[pos] = Some(piece);
-    }
 }
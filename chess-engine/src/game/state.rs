@@ -0,0 +1,120 @@
+use std::fmt;
+
+use crate::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Ongoing,
+    /// The game is not over, but the player to move may claim a draw with
+    /// `Game::claim_draw`
+    DrawClaimable(DrawReason),
+    Win {
+        winner: Color,
+        reason: WinReason,
+    },
+    Draw(DrawReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinReason {
+    Checkmate,
+    Resignation,
+    /// The loser ran out of time
+    Timeout,
+    /// The result was decided by an arbiter or by agreement outside of the
+    /// game, e.g. when importing a game that ended in an unknown way
+    Adjudication,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    Stalemate,
+    /// No capture or pawn move has been made in the last fifty moves by each
+    /// player
+    FiftyMoveRule,
+    /// No capture or pawn move has been made in the last seventy-five moves by
+    /// each player
+    SeventyFiveMoveRule,
+    /// Neither player has the material needed to checkmate the other. See
+    /// `Board::has_insufficient_material`
+    InsufficientMaterial,
+    /// The same position has occurred three times
+    ThreefoldRepetition,
+    /// The same position has occurred five times
+    FivefoldRepetition,
+    Agreement,
+    /// A player ran out of time, but the opponent could not have checkmated
+    /// them
+    Timeout,
+    /// See `WinReason::Adjudication`
+    Adjudication,
+}
+
+impl GameState {
+    /// Whether more moves can be made, i.e. the state is either `Ongoing` or
+    /// `DrawClaimable`
+    pub fn is_ongoing(&self) -> bool {
+        matches!(self, Self::Ongoing | Self::DrawClaimable(_))
+    }
+    /// Returns the winner of the game, if it is over and was not drawn
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Self::Win { winner, .. } => Some(*winner),
+            _ => None,
+        }
+    }
+    /// Returns the result as written in PGN, i.e. "1-0", "0-1", "1/2-1/2" or
+    /// "*" for a game that is not over
+    pub fn result(&self) -> &'static str {
+        match self {
+            Self::Ongoing | Self::DrawClaimable(_) => "*",
+            Self::Win {
+                winner: Color::White,
+                ..
+            } => "1-0",
+            Self::Win {
+                winner: Color::Black,
+                ..
+            } => "0-1",
+            Self::Draw(_) => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ongoing => write!(f, "Ongoing"),
+            Self::DrawClaimable(reason) => write!(f, "Draw can be claimed by {}", reason),
+            Self::Win { winner, reason } => write!(f, "{:?} wins by {}", winner, reason),
+            Self::Draw(reason) => write!(f, "Draw by {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for WinReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Checkmate => write!(f, "checkmate"),
+            Self::Resignation => write!(f, "resignation"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Adjudication => write!(f, "adjudication"),
+        }
+    }
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stalemate => write!(f, "stalemate"),
+            Self::FiftyMoveRule => write!(f, "the fifty-move rule"),
+            Self::SeventyFiveMoveRule => write!(f, "the seventy-five-move rule"),
+            Self::InsufficientMaterial => write!(f, "insufficient material"),
+            Self::ThreefoldRepetition => write!(f, "threefold repetition"),
+            Self::FivefoldRepetition => write!(f, "fivefold repetition"),
+            Self::Agreement => write!(f, "agreement"),
+            Self::Timeout => write!(f, "timeout vs insufficient material"),
+            Self::Adjudication => write!(f, "adjudication"),
+        }
+    }
+}
@@ -2,17 +2,22 @@
 
 #![deny(warnings)]
 
+mod bitboard;
 mod board;
 mod decider;
 mod error;
+pub mod eval;
 mod game;
+pub mod perft;
+pub mod pgn;
 pub mod piece;
+pub mod search;
 pub mod util;
 
-pub use board::Board;
+pub use board::{Board, MoveRecord};
 pub use decider::Decider;
 pub use error::Error;
-pub use game::{Game, GameState};
+pub use game::{DrawReason, Game, GameState, WinReason};
 pub use piece::Piece;
 pub use util::{Color, Move, Position};
 
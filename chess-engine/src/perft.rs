@@ -0,0 +1,266 @@
+//! Counting the positions reachable in a number of moves, to verify move
+//! generation against the known counts, or against other engines.
+//!
+//! # Example use:
+//! ```rust
+//! # use chess_engine::{perft, Board};
+//! assert_eq!(perft::perft(&Board::default(), 3), 8902);
+//!
+//! let entry = perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - ;D1 15 ;D2 66").unwrap();
+//! assert_eq!(entry.nodes, vec![(1, 15), (2, 66)]);
+//! ```
+
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{Board, Error, Move};
+
+/// Returns the number of leaf nodes of the tree of legal moves `depth` plies
+/// deep, starting from `board`
+pub fn perft(board: &Board, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    count(&mut board.clone(), depth)
+}
+
+/// Returns every legal move together with the number of leaf nodes after
+/// it, i.e. `perft` split up by the first move. The counts add up to
+/// `perft(board, depth)`, except for depth 0 where no moves are returned.
+pub fn divide(board: &Board, depth: usize) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut board = board.clone();
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|m| {
+            let record = board.make_move_unchecked(m);
+            let nodes = if depth == 1 {
+                1
+            } else {
+                count(&mut board, depth - 1)
+            };
+            board.unmake_move(&record);
+            (m, nodes)
+        })
+        .collect()
+}
+
+fn count(board: &mut Board, depth: usize) -> u64 {
+    let moves = board.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for m in moves {
+        let record = board.make_move_unchecked(m);
+        nodes += count(board, depth - 1);
+        board.unmake_move(&record);
+    }
+    nodes
+}
+
+/// Runs perft on several threads, optionally with a hash table of node
+/// counts for positions that are reached through different move orders. The
+/// results are the same as those of `perft` and `divide`.
+///
+/// ```rust
+/// # use chess_engine::{perft::{self, Perft}, Board};
+/// let perft = Perft::new().threads(4).hash_table(16);
+/// assert_eq!(perft.perft(&Board::default(), 4), perft::perft(&Board::default(), 4));
+/// ```
+#[derive(Debug)]
+pub struct Perft {
+    threads: usize,
+    table: Option<Table>,
+}
+
+impl Perft {
+    /// Uses a single thread and no hash table
+    pub fn new() -> Self {
+        Self {
+            threads: 1,
+            table: None,
+        }
+    }
+    /// Splits the moves from the root position between `threads` threads
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    /// Uses a hash table of about `megabytes` MB, shared by all threads. The
+    /// table is kept between calls.
+    pub fn hash_table(mut self, megabytes: usize) -> Self {
+        self.table = Table::new(megabytes);
+        self
+    }
+    pub fn perft(&self, board: &Board, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        self.divide(board, depth)
+            .iter()
+            .map(|(_, nodes)| nodes)
+            .sum()
+    }
+    /// See `divide`. The moves are returned in the same order.
+    pub fn divide(&self, board: &Board, depth: usize) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let moves = board.legal_moves();
+        let next = AtomicUsize::new(0);
+        let mut nodes = vec![0; moves.len()];
+
+        thread::scope(|scope| {
+            let workers = (0..self.threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut board = board.clone();
+                        let mut counted = Vec::new();
+                        // Take moves until there are none left
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let m = match moves.get(i) {
+                                Some(&m) => m,
+                                None => break counted,
+                            };
+                            let record = board.make_move_unchecked(m);
+                            let n = match &self.table {
+                                _ if depth == 1 => 1,
+                                Some(table) => count_hashed(&mut board, depth - 1, table),
+                                None => count(&mut board, depth - 1),
+                            };
+                            board.unmake_move(&record);
+                            counted.push((i, n));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for worker in workers {
+                for (i, n) in worker.join().unwrap() {
+                    nodes[i] = n;
+                }
+            }
+        });
+
+        moves.into_iter().zip(nodes).collect()
+    }
+}
+
+impl Default for Perft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn count_hashed(board: &mut Board, depth: usize, table: &Table) -> u64 {
+    if depth == 1 {
+        return board.legal_moves().len() as u64;
+    }
+    let key = board.zobrist_key();
+    if let Some(nodes) = table.get(key, depth) {
+        return nodes;
+    }
+    let mut nodes = 0;
+    for m in board.legal_moves() {
+        let record = board.make_move_unchecked(m);
+        nodes += count_hashed(board, depth - 1, table);
+        board.unmake_move(&record);
+    }
+    table.insert(key, depth, nodes);
+    nodes
+}
+
+/// A hash table of node counts that can be shared between threads without
+/// locking. Each entry stores the key XOR:ed with the data, so that an entry
+/// that is torn by two threads writing to it at once is detected as not
+/// matching the key.
+#[derive(Debug)]
+struct Table {
+    entries: Vec<(AtomicU64, AtomicU64)>,
+}
+
+impl Table {
+    /// Returns `None` for a size of zero
+    fn new(megabytes: usize) -> Option<Self> {
+        let len = megabytes * 1024 * 1024 / std::mem::size_of::<(AtomicU64, AtomicU64)>();
+        if len == 0 {
+            return None;
+        }
+        let entries = (0..len)
+            .map(|_| (AtomicU64::new(0), AtomicU64::new(0)))
+            .collect();
+        Some(Self { entries })
+    }
+    fn entry(&self, key: u64) -> &(AtomicU64, AtomicU64) {
+        &self.entries[(key % self.entries.len() as u64) as usize]
+    }
+    fn get(&self, key: u64, depth: usize) -> Option<u64> {
+        let (checksum, data) = self.entry(key);
+        let data = data.load(Ordering::Relaxed);
+        let checksum = checksum.load(Ordering::Relaxed);
+        // The depth is stored in the lowest 8 bits, the node count in the rest
+        if checksum ^ data == key && data & 0xFF == depth as u64 {
+            Some(data >> 8)
+        } else {
+            None
+        }
+    }
+    fn insert(&self, key: u64, depth: usize, nodes: u64) {
+        // Node counts that don't fit, and depths that can't be searched in a
+        // lifetime, are just not stored
+        if depth > 0xFF || nodes >> 56 != 0 {
+            return;
+        }
+        let data = nodes << 8 | depth as u64;
+        let (checksum, entry) = self.entry(key);
+        checksum.store(key ^ data, Ordering::Relaxed);
+        entry.store(data, Ordering::Relaxed);
+    }
+}
+
+/// A line of an EPD perft suite: a position and the expected number of leaf
+/// nodes at some depths, e.g.
+/// `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ;D1 20 ;D2 400`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdEntry {
+    pub board: Board,
+    /// Pairs of depth and node count
+    pub nodes: Vec<(usize, u64)>,
+}
+
+impl EpdEntry {
+    /// Parses a line of an EPD perft suite. The position may be given with or
+    /// without the halfmove counter and move number.
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let mut parts = line.split(';');
+        let fen = parts.next().unwrap_or("").trim();
+        let board = if fen.split_ascii_whitespace().count() == 4 {
+            Board::from_fen(&format!("{} 0 1", fen))?
+        } else {
+            Board::from_fen(fen)?
+        };
+
+        let nodes = parts
+            .map(|part| {
+                let mut words = part.split_ascii_whitespace();
+                let depth = words
+                    .next()
+                    .and_then(|d| d.strip_prefix('D'))
+                    .and_then(|d| d.parse().ok());
+                let nodes = words.next().and_then(|n| n.parse().ok());
+                match (depth, nodes, words.next()) {
+                    (Some(depth), Some(nodes), None) => Ok((depth, nodes)),
+                    _ => Err(Error::ParsingError),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { board, nodes })
+    }
+}
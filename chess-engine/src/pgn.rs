@@ -0,0 +1,598 @@
+//! Reading and writing games in Portable Game Notation (PGN).
+//!
+//! # Example use:
+//! ```rust
+//! # use chess_engine::pgn::{Annotation, Reader};
+//! let pgn = r#"[Event "Paris"]
+//! [Result "1-0"]
+//!
+//! 1. e4 e5 2. Nf3 d6 {Philidor Defence} 3. d4 Bg4?! (3... exd4) 1-0
+//! "#;
+//!
+//! for game in Reader::new(pgn.as_bytes()) {
+//!     let game = game.unwrap();
+//!     assert_eq!(game.tag("Event"), Some("Paris"));
+//!     assert_eq!(game.game.history().len(), 6);
+//!     assert_eq!(game.annotations[4], [Annotation::Comment("Philidor Defence".to_owned())]);
+//!     assert_eq!(game.annotations[6], [Annotation::Nag(6)]);
+//!     println!("{}", game.to_pgn());
+//! }
+//! ```
+
+use std::io::{self, BufRead};
+
+use crate::{error::PgnError, Board, Color, Error, Game, GameState, Move};
+
+/// The tags of the Seven Tag Roster, which are always exported, in order
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A game together with the tag pairs describing it, e.g. who played it.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    /// The annotations of each position of the game: `annotations[0]` come
+    /// before the first move, and `annotations[i + 1]` follow
+    /// `game.history()[i]`
+    pub annotations: Vec<Vec<Annotation>>,
+}
+
+/// A remark about a move, or about the starting position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Annotation {
+    /// The text of a comment, without the braces or the semicolon
+    Comment(String),
+    /// A Numeric Annotation Glyph, e.g. 1 for a good move. Suffix annotations
+    /// such as "!?" are read as the NAG they stand for.
+    Nag(u8),
+}
+
+impl PgnGame {
+    /// Creates a `PgnGame` without any tags or annotations. Tags in the Seven
+    /// Tag Roster are exported with unknown values unless set.
+    pub fn new(game: Game) -> Self {
+        Self {
+            tags: Vec::new(),
+            annotations: vec![Vec::new(); game.history().len() + 1],
+            game,
+        }
+    }
+    /// Returns the value of the tag named `name`, if any
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Sets the value of the tag named `name`, replacing any previous value
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+    /// Parses a single game. To read several games, e.g. from a file, use
+    /// `Reader`.
+    ///
+    /// The moves are made on a `Game` starting from the position in the
+    /// `FEN` tag, if present. Comments and NAGs of the main line are kept in
+    /// `annotations`. The moves of variations are checked, but variations and
+    /// their annotations are not kept. If the result of the game is not
+    /// decided by the moves, the game is ended according to the result token,
+    /// see `Game::adjudicate`.
+    pub fn from_pgn(pgn: &str) -> Result<Self, Error> {
+        parse(pgn, 1)
+    }
+    /// Returns the game in PGN export format. The `Result` tag is set from
+    /// the state of the game, and `SetUp` and `FEN` tags are added if the game
+    /// did not start from the default position.
+    pub fn to_pgn(&self) -> String {
+        let initial_board = self.game.initial_board();
+        let result = self.game.state().result();
+
+        let mut tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&name| {
+                let value = match name {
+                    "Result" => result,
+                    "Date" => self.tag(name).unwrap_or("????.??.??"),
+                    _ => self.tag(name).unwrap_or("?"),
+                };
+                (name, value.to_owned())
+            })
+            .collect::<Vec<_>>();
+        let initial_fen = initial_board.to_fen();
+        if initial_board != &Board::default() {
+            tags.push(("SetUp", "1".to_owned()));
+            tags.push(("FEN", initial_fen));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(n, _)| n == name) {
+                tags.push((name, value.clone()));
+            }
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut board = initial_board.clone();
+        let mut tokens = Vec::new();
+        // Black's moves are numbered at the start and after comments
+        let mut number_black = self.push_annotations(0, &mut tokens);
+        for (i, record) in self.game.history().iter().enumerate() {
+            if board.next_to_move() == Color::White {
+                tokens.push(format!("{}.", board.move_number));
+            } else if i == 0 || number_black {
+                tokens.push(format!("{}...", board.move_number));
+            }
+            tokens.push(record.move_().as_algebraic(&board).unwrap());
+            board.make_move_unchecked(record.move_());
+            number_black = self.push_annotations(i + 1, &mut tokens);
+        }
+        tokens.push(result.to_owned());
+
+        // Lines in export format must not be longer than 79 characters
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len != 0 && line_len + 1 + token.len() > 79 {
+                pgn.push('\n');
+                line_len = 0;
+            } else if line_len != 0 {
+                pgn.push(' ');
+                line_len += 1;
+            }
+            line_len += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+    /// Adds the annotations of the position after `ply` moves to `tokens`,
+    /// with comments split into words. Returns whether there were comments.
+    fn push_annotations(&self, ply: usize, tokens: &mut Vec<String>) -> bool {
+        let annotations = self.annotations.get(ply).map_or(&[][..], Vec::as_slice);
+        for annotation in annotations {
+            match annotation {
+                Annotation::Comment(text) => {
+                    let mut words = text.split_whitespace().collect::<Vec<_>>();
+                    if words.is_empty() {
+                        words.push("");
+                    }
+                    let last = words.len() - 1;
+                    for (i, word) in words.into_iter().enumerate() {
+                        let open = if i == 0 { "{" } else { "" };
+                        let close = if i == last { "}" } else { "" };
+                        tokens.push(format!("{}{}{}", open, word, close));
+                    }
+                }
+                Annotation::Nag(nag) => tokens.push(format!("${}", nag)),
+            }
+        }
+        annotations
+            .iter()
+            .any(|annotation| matches!(annotation, Annotation::Comment(_)))
+    }
+}
+
+/// Reads games one at a time from PGN text, e.g. a file containing several
+/// games, without keeping more than one game in memory.
+pub struct Reader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    /// A line, and its line number, that has been read but belongs to the
+    /// next game
+    pending: Option<(usize, String)>,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<PgnGame, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::new();
+        let mut first_line = 0;
+        let mut in_movetext = false;
+        let mut scanner = Scanner::default();
+        loop {
+            let (line_number, line) = match self.pending.take() {
+                Some(pending) => pending,
+                None => match self.lines.next() {
+                    Some(Ok(line)) => {
+                        self.line += 1;
+                        (self.line, line)
+                    }
+                    Some(Err(_)) => return Some(Err(Error::PgnError(PgnError::Io))),
+                    None => break,
+                },
+            };
+            let trimmed = line.trim();
+            if text.is_empty() {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                first_line = line_number;
+            }
+            // A tag after the movetext starts the next game, unless it's part
+            // of a comment
+            if in_movetext && trimmed.starts_with('[') && !scanner.in_comment {
+                self.pending = Some((line_number, line));
+                break;
+            }
+            if !trimmed.is_empty() && !trimmed.starts_with('[') && !trimmed.starts_with('%') {
+                in_movetext = true;
+            }
+            scanner.scan(&line);
+            text.push_str(&line);
+            text.push('\n');
+            if in_movetext && scanner.complete {
+                break;
+            }
+        }
+        if text.is_empty() {
+            None
+        } else {
+            Some(parse(&text, first_line))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    /// A tag pair with the name and unescaped value
+    Tag(&'a str, String),
+    MoveNumber,
+    San(&'a str),
+    Nag(u8),
+    /// The text of a comment
+    Comment(&'a str),
+    VariationStart,
+    VariationEnd,
+    Result(&'a str),
+}
+
+/// Splits PGN text into tokens, along with the line number they are on.
+struct Tokens<'a> {
+    s: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(s: &'a str, first_line: usize) -> Self {
+        Self {
+            s,
+            pos: 0,
+            line: first_line,
+        }
+    }
+    fn peek_byte(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+    fn next_byte(&mut self) -> Option<u8> {
+        let c = self.peek_byte()?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+    /// Skips past the next newline, or to the end of the text
+    fn skip_line(&mut self) {
+        while !matches!(self.next_byte(), Some(b'\n') | None) {}
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek_byte().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.next_byte();
+        }
+    }
+    fn tag(&mut self) -> Result<Token<'a>, Error> {
+        let line = self.line;
+        let err = || Error::PgnError(PgnError::Tag { line });
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek_byte()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.next_byte();
+        }
+        let name = &self.s[start..self.pos];
+        self.skip_whitespace();
+        if name.is_empty() || self.next_byte() != Some(b'"') {
+            return Err(err());
+        }
+        let mut value = Vec::new();
+        loop {
+            match self.next_byte() {
+                Some(b'"') => break,
+                Some(b'\\') => value.push(self.next_byte().ok_or_else(err)?),
+                Some(b'\n') | None => return Err(err()),
+                Some(c) => value.push(c),
+            }
+        }
+        self.skip_whitespace();
+        if self.next_byte() != Some(b']') {
+            return Err(err());
+        }
+        let value = String::from_utf8(value).map_err(|_| err())?;
+        Ok(Token::Tag(name, value))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<(usize, Token<'a>), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.skip_whitespace();
+            // Lines starting with '%' are escaped and should be ignored
+            if self.peek_byte() == Some(b'%')
+                && (self.pos == 0 || self.s.as_bytes()[self.pos - 1] == b'\n')
+            {
+                self.skip_line();
+            } else {
+                break;
+            }
+        }
+        let line = self.line;
+        let token = match self.next_byte()? {
+            b'[' => match self.tag() {
+                Ok(tag) => tag,
+                Err(err) => return Some(Err(err)),
+            },
+            b'{' => {
+                let start = self.pos;
+                loop {
+                    match self.next_byte() {
+                        Some(b'}') => break Token::Comment(&self.s[start..self.pos - 1]),
+                        Some(_) => {}
+                        None => return Some(Err(Error::PgnError(PgnError::UnterminatedComment))),
+                    }
+                }
+            }
+            b';' => {
+                let start = self.pos;
+                self.skip_line();
+                Token::Comment(self.s[start..self.pos].trim_end_matches('\n'))
+            }
+            b'(' => Token::VariationStart,
+            b')' => Token::VariationEnd,
+            b'*' => Token::Result("*"),
+            b'$' => {
+                let start = self.pos;
+                while self.peek_byte().is_some_and(|c| c.is_ascii_digit()) {
+                    self.next_byte();
+                }
+                match self.s[start..self.pos].parse() {
+                    Ok(nag) => Token::Nag(nag),
+                    Err(_) => return Some(Err(Error::PgnError(PgnError::Token { line }))),
+                }
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let start = self.pos - 1;
+                while self
+                    .peek_byte()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || b"-/=+#!?_:".contains(&c))
+                {
+                    self.next_byte();
+                }
+                let symbol = &self.s[start..self.pos];
+                if symbol.bytes().all(|c| c.is_ascii_digit()) && self.peek_byte() == Some(b'.') {
+                    while self.peek_byte() == Some(b'.') {
+                        self.next_byte();
+                    }
+                    Token::MoveNumber
+                } else if matches!(symbol, "1-0" | "0-1" | "1/2-1/2") {
+                    Token::Result(symbol)
+                } else {
+                    Token::San(symbol)
+                }
+            }
+            _ => return Some(Err(Error::PgnError(PgnError::Token { line }))),
+        };
+        Some(Ok((line, token)))
+    }
+}
+
+/// Finds where a game ends, reading its text one line at a time
+#[derive(Debug, Default)]
+struct Scanner {
+    /// How many variations are open
+    depth: i32,
+    /// Whether the last line ends inside of a comment
+    in_comment: bool,
+    /// Whether the movetext has ended with a result token
+    complete: bool,
+}
+
+impl Scanner {
+    fn scan(&mut self, line: &str) {
+        let mut tokens = Tokens::new(line, 1);
+        if self.in_comment {
+            match line.find('}') {
+                Some(end) => tokens.pos = end + 1,
+                None => return,
+            }
+            self.in_comment = false;
+        }
+        for token in tokens {
+            match token {
+                Ok((_, Token::VariationStart)) => self.depth += 1,
+                Ok((_, Token::VariationEnd)) => self.depth -= 1,
+                Ok((_, Token::Result(_))) if self.depth == 0 => {
+                    self.complete = true;
+                    return;
+                }
+                Err(Error::PgnError(PgnError::UnterminatedComment)) => {
+                    self.in_comment = true;
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse(text: &str, first_line: usize) -> Result<PgnGame, Error> {
+    let mut tokens = Tokens::new(text, first_line).peekable();
+
+    let mut tags = Vec::new();
+    while let Some(Ok((_, Token::Tag(..)))) = tokens.peek() {
+        if let Some(Ok((_, Token::Tag(name, value)))) = tokens.next() {
+            tags.push((name.to_owned(), value));
+        }
+    }
+    let mut pgn_game = PgnGame {
+        game: Game::new(Board::default()),
+        tags,
+        annotations: Vec::new(),
+    };
+    if let Some(fen) = pgn_game.tag("FEN") {
+        pgn_game.game = Game::new(Board::from_fen(fen)?);
+    }
+    let result_tag = pgn_game.tag("Result").map(str::to_owned);
+    let game = &mut pgn_game.game;
+    let mut annotations = vec![Vec::new()];
+
+    // The variations being read, innermost last. Moves in them are checked
+    // but not kept.
+    let mut variations: Vec<Variation> = Vec::new();
+    // The position before the last move of the main line, where a variation
+    // of it starts from
+    let mut previous = None;
+    let mut result = None;
+    for token in tokens {
+        let (line, token) = token?;
+        if result.is_some() {
+            return Err(Error::PgnError(PgnError::Token { line }));
+        }
+        match token {
+            Token::MoveNumber => {}
+            Token::Nag(_) | Token::Comment(_) if !variations.is_empty() => {}
+            Token::Nag(nag) => annotations.last_mut().unwrap().push(Annotation::Nag(nag)),
+            Token::Comment(text) => {
+                let comment = Annotation::Comment(text.trim().to_owned());
+                annotations.last_mut().unwrap().push(comment);
+            }
+            Token::VariationStart => {
+                let start = match variations.last() {
+                    Some(variation) => variation.previous.clone(),
+                    None => previous.clone(),
+                };
+                let board = start.ok_or(Error::PgnError(PgnError::Token { line }))?;
+                variations.push(Variation {
+                    previous: None,
+                    board,
+                });
+            }
+            Token::VariationEnd => {
+                variations
+                    .pop()
+                    .ok_or(Error::PgnError(PgnError::Token { line }))?;
+            }
+            Token::San(san) => {
+                let err = |_| {
+                    Error::PgnError(PgnError::Move {
+                        line,
+                        san: san.to_owned(),
+                    })
+                };
+                match variations.last_mut() {
+                    Some(variation) => {
+                        let m = Move::from_san(san, &variation.board).map_err(err)?;
+                        let board = variation.board.clone();
+                        variation.board.make_move_unchecked(m);
+                        variation.previous = Some(board);
+                    }
+                    None => {
+                        let m = Move::from_san(san, game.board()).map_err(err)?;
+                        let board = game.board().clone();
+                        game.make_move(m).map_err(err)?;
+                        previous = Some(board);
+
+                        let suffix = &san[san.find(['!', '?']).unwrap_or(san.len())..];
+                        let mut move_annotations = Vec::new();
+                        if !suffix.is_empty() {
+                            let nag = suffix_nag(suffix)
+                                .ok_or(Error::PgnError(PgnError::Token { line }))?;
+                            move_annotations.push(Annotation::Nag(nag));
+                        }
+                        annotations.push(move_annotations);
+                    }
+                }
+            }
+            Token::Result(_) if !variations.is_empty() => {
+                return Err(Error::PgnError(PgnError::Token { line }))
+            }
+            Token::Result(r) => {
+                let err = Error::PgnError(PgnError::Result { line });
+                if result_tag.as_ref().is_some_and(|tag| tag != r) {
+                    return Err(err);
+                }
+                end_game(game, r).map_err(|_| err)?;
+                result = Some(r);
+            }
+            Token::Tag(..) => return Err(Error::PgnError(PgnError::Token { line })),
+        }
+    }
+    if !variations.is_empty() {
+        return Err(Error::PgnError(PgnError::UnterminatedVariation));
+    }
+
+    pgn_game.annotations = annotations;
+    Ok(pgn_game)
+}
+
+/// Returns the NAG that a suffix annotation such as "!?" stands for
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// A variation being read, i.e. moves played instead of the last move of the
+/// line it is in
+struct Variation {
+    /// The position before the last move of the variation, where a variation
+    /// of it starts from
+    previous: Option<Board>,
+    board: Board,
+}
+
+/// Ends `game` according to the result token `result`, if the game is not
+/// already over. Fails if the game is over with another result.
+fn end_game(game: &mut Game, result: &str) -> Result<(), Error> {
+    let state = game.state();
+    if !state.is_ongoing() {
+        return if state.result() == result {
+            Ok(())
+        } else {
+            Err(Error::GameOver)
+        };
+    }
+    match (result, state) {
+        ("*", _) => Ok(()),
+        ("1/2-1/2", GameState::DrawClaimable(_)) => game.claim_draw().map(|_| ()),
+        ("1/2-1/2", _) => game.adjudicate(None).map(|_| ()),
+        ("1-0", _) => game.adjudicate(Some(Color::White)).map(|_| ()),
+        (_, _) => game.adjudicate(Some(Color::Black)).map(|_| ()),
+    }
+}
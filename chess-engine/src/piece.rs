@@ -1,11 +1,8 @@
-use crate::{Board, Color, Error, Position};
+use crate::{
+    bitboard::{self, Bitboard},
+    Board, Color, Error, Position,
+};
 
-mod bishop;
-mod king;
-mod knight;
-mod pawn;
-mod queen;
-mod rook;
 pub mod util;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -24,26 +21,14 @@ pub enum Kind {
     King,
 }
 
-pub enum Moves<'b> {
-    Pawn(pawn::Moves<'b>),
-    Rook(rook::Moves<'b>),
-    Knight(knight::Moves<'b>),
-    Bishop(bishop::Moves<'b>),
-    Queen(queen::Moves<'b>),
-    King(king::Moves<'b>),
-}
+/// The squares a piece can legally move to, see `Piece::moves` and
+/// `Piece::captures`
+pub struct Moves(Bitboard);
 
-impl<'b> Iterator for Moves<'b> {
+impl Iterator for Moves {
     type Item = Position;
     fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            Self::Pawn(moves) => moves.next(),
-            Self::Rook(moves) => moves.next(),
-            Self::Knight(moves) => moves.next(),
-            Self::Bishop(moves) => moves.next(),
-            Self::Queen(moves) => moves.next(),
-            Self::King(moves) => moves.next(),
-        }
+        self.0.next()
     }
 }
 
@@ -86,29 +71,36 @@ impl Piece {
             self.kind.name()
         }
     }
-    pub fn moves<'b>(&self, board: &'b Board, from: Position) -> Moves<'b> {
-        match self.kind {
-            Kind::Pawn => Moves::Pawn(pawn::Moves::new(board, from)),
-            Kind::Rook => Moves::Rook(rook::Moves::new(board, from)),
-            Kind::Knight => Moves::Knight(knight::Moves::new(board, from)),
-            Kind::Bishop => Moves::Bishop(bishop::Moves::new(board, from)),
-            Kind::Queen => Moves::Queen(queen::Moves::new(board, from)),
-            Kind::King => Moves::King(king::Moves::new(board, from)),
-        }
+    /// Returns the squares this piece, standing on `from`, can legally move
+    /// to, as if it was its turn
+    pub fn moves(&self, board: &Board, from: Position) -> Moves {
+        Moves(board.move_gen(self.color).targets(*self, from))
     }
+    /// Returns the squares this piece, standing on `from`, can legally move
+    /// to with a capture or a promotion, as if it was its turn. See `moves`.
+    pub fn captures(&self, board: &Board, from: Position) -> Moves {
+        Moves(board.move_gen(self.color).capture_targets(*self, from))
+    }
+    /// Whether this piece, placed at `at`, attacks the opponent's king
     pub fn checks(&self, at: Position, board: &Board) -> bool {
-        match self.kind {
-            Kind::Pawn => pawn::checks(at, self.color, board),
-            Kind::Rook => rook::checks(at, self.color, board),
-            Kind::Knight => knight::checks(at, self.color, board),
-            Kind::Bishop => bishop::checks(at, self.color, board),
-            Kind::Queen => queen::checks(at, self.color, board),
-            Kind::King => king::checks(at, self.color, board),
-        }
+        let occupied = board.occupied();
+        let attacks = match self.kind {
+            Kind::Pawn => bitboard::pawn_attacks(self.color, at),
+            Kind::Knight => bitboard::knight_attacks(at),
+            Kind::Bishop => bitboard::bishop_attacks(at, occupied),
+            Kind::Rook => bitboard::rook_attacks(at, occupied),
+            Kind::Queen => bitboard::queen_attacks(at, occupied),
+            // A king can never check the other king
+            Kind::King => return false,
+        };
+        attacks.contains(board.get_king_position(self.color.other()))
     }
 }
 
 impl Kind {
+    /// The kinds of pieces a pawn can be promoted to
+    pub const PROMOTIONS: [Kind; 4] = [Kind::Queen, Kind::Rook, Kind::Bishop, Kind::Knight];
+
     pub fn from_name(name: char) -> Result<Self, Error> {
         match name {
             'p' | 'P' => Ok(Self::Pawn),
@@ -130,4 +122,16 @@ impl Kind {
             Self::King => 'K',
         }
     }
+    /// The usual value of the kind of piece in centipawns. The king is worth
+    /// more than all other pieces together.
+    pub fn value(&self) -> i32 {
+        match *self {
+            Self::Pawn => 100,
+            Self::Rook => 500,
+            Self::Knight => 320,
+            Self::Bishop => 330,
+            Self::Queen => 900,
+            Self::King => 20_000,
+        }
+    }
 }
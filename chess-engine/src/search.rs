@@ -0,0 +1,403 @@
+//! Finding the best move in a position by searching the tree of moves with
+//! negamax alpha-beta and iterative deepening, followed by a quiescence
+//! search of captures.
+//!
+//! # Example use:
+//! ```rust
+//! # use chess_engine::{search::{Engine, Limit, MATE}, Board, Move};
+//! let board = Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap();
+//! let result = Engine::new(Limit::Depth(3)).search(&board);
+//! assert_eq!(result.best_move, Some(Move::arabic("h1h8").unwrap()));
+//! assert_eq!(result.score, MATE - 1);
+//! ```
+
+use crate::{eval::evaluate, piece::Kind, Board, Decider, Game, Move};
+
+mod tt;
+
+pub use tt::{Bound, Entry, TranspositionTable};
+
+/// The score of checkmating the opponent right away. Checkmating in `n` plies
+/// scores `MATE - n`, and being checkmated in `n` plies scores `n - MATE`.
+pub const MATE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+/// How deep to search at most, also when limited by nodes
+const MAX_PLY: usize = 64;
+/// How much more than the material it wins a capture has to be able to gain
+/// in quiescence search to not be pruned
+const DELTA_MARGIN: i32 = 200;
+/// The size of the transposition table of a new `Engine`, in MB
+const DEFAULT_HASH_SIZE: usize = 16;
+
+/// When to stop searching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Search this many plies deep
+    Depth(usize),
+    /// Search deeper until about this many positions have been visited. The
+    /// result of the last search that was not cut short is used.
+    Nodes(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// `None` if there are no legal moves
+    pub best_move: Option<Move>,
+    /// The score of `best_move` in centipawns, from the view of the player to
+    /// move. See `MATE` for how checkmates are scored.
+    pub score: i32,
+    /// The depth of the deepest completed search
+    pub depth: usize,
+    /// The number of positions visited, including those of a search that was
+    /// cut short
+    pub nodes: u64,
+    /// The principal variation, i.e. the moves both players are expected to
+    /// make, starting with `best_move`
+    pub pv: Vec<Move>,
+}
+
+/// A computer player. Searches with negamax alpha-beta, deepening the search
+/// one ply at a time and trying the principal variation of the previous
+/// search first. Positions that have already been searched are looked up in
+/// a transposition table, which is kept between searches.
+///
+/// ```no_run
+/// # use chess_engine::{search::{Engine, Limit}, Board, Decider};
+/// // Let the engine play against itself
+/// let state = Engine::new(Limit::Nodes(100_000)).run(Board::default());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Engine {
+    limit: Limit,
+    nodes: u64,
+    /// The number of nodes at which to abandon the current search
+    max_nodes: u64,
+    /// Whether `max_nodes` has been reached
+    stopped: bool,
+    /// The keys of the positions from the root to the current one, to detect
+    /// repetitions
+    keys: Vec<u64>,
+    table: TranspositionTable,
+}
+
+impl Engine {
+    pub fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            nodes: 0,
+            max_nodes: u64::MAX,
+            stopped: false,
+            keys: Vec::new(),
+            table: TranspositionTable::new(DEFAULT_HASH_SIZE),
+        }
+    }
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+    pub fn set_limit(&mut self, limit: Limit) {
+        self.limit = limit;
+    }
+    /// Replaces the transposition table with an empty one of about
+    /// `megabytes` MB. The default is 16 MB.
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.table = TranspositionTable::new(megabytes);
+    }
+    /// Forgets the positions searched so far, before a new game
+    pub fn new_game(&mut self) {
+        self.table.clear();
+    }
+    /// Searches for the best move for the player to move on `board`. Only
+    /// repetitions of positions reached during the search are detected, see
+    /// `search_game`.
+    pub fn search(&mut self, board: &Board) -> SearchResult {
+        self.search_with_history(board, &[board.position_key()])
+    }
+    /// Searches for the best move for the player to move in `game`. Unlike
+    /// `search`, moves that repeat a position from earlier in the game are
+    /// scored as draws.
+    pub fn search_game(&mut self, game: &Game) -> SearchResult {
+        self.search_with_history(game.board(), game.positions())
+    }
+    /// `history` is the `Board::position_key` of every position up to and
+    /// including `board`
+    fn search_with_history(&mut self, board: &Board, history: &[u64]) -> SearchResult {
+        let mut board = board.clone();
+        self.nodes = 0;
+        self.stopped = false;
+        self.keys = history.to_vec();
+        self.table.new_search();
+
+        let max_depth = match self.limit {
+            Limit::Depth(depth) => depth.clamp(1, MAX_PLY),
+            Limit::Nodes(_) => MAX_PLY,
+        };
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+        for depth in 1..=max_depth {
+            // The first search is always completed, so that there is a move
+            self.max_nodes = match self.limit {
+                Limit::Nodes(nodes) if depth > 1 => nodes,
+                _ => u64::MAX,
+            };
+            let mut pv = Vec::new();
+            let score = self.negamax(
+                &mut board, depth, 0, -INFINITY, INFINITY, &result.pv, &mut pv,
+            );
+            if self.stopped {
+                break;
+            }
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                depth,
+                nodes: self.nodes,
+                pv,
+            };
+            // Searching deeper can't find a faster mate
+            if score.abs() >= MATE - depth as i32 {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result
+    }
+    /// Returns the score of `board` from the view of the player to move,
+    /// searching `depth` plies deep. `board` is `ply` plies from the root.
+    /// `pv_hint` is the principal variation from this position found by the
+    /// previous search, tried first. The principal variation found is put in
+    /// `pv`.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: usize,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv_hint: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        if ply > 0 && self.is_draw(board) {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.nodes >= self.max_nodes {
+            self.stopped = true;
+            return 0;
+        }
+
+        let key = board.zobrist_key();
+        let entry = self.table.get(key, ply);
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth as usize >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                pv.extend(entry.best_move);
+                return entry.score;
+            }
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.is_check() {
+                ply as i32 - MATE
+            } else {
+                0
+            };
+        }
+        if ply >= MAX_PLY {
+            return evaluate(board);
+        }
+        let first = pv_hint
+            .first()
+            .copied()
+            .or_else(|| entry.and_then(|entry| entry.best_move));
+        order_moves(board, &mut moves, first);
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for m in moves {
+            let hint = match pv_hint.split_first() {
+                Some((&first, rest)) if first == m => rest,
+                _ => &[],
+            };
+            let record = board.make_move_unchecked(m);
+            self.keys.push(board.position_key());
+            child_pv.clear();
+            let score = -self.negamax(
+                board,
+                depth - 1,
+                ply + 1,
+                -beta,
+                -alpha,
+                hint,
+                &mut child_pv,
+            );
+            self.keys.pop();
+            board.unmake_move(&record);
+
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(m);
+                    pv.clear();
+                    pv.push(m);
+                    pv.extend_from_slice(&child_pv);
+                }
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table.insert(key, depth, bound, best, ply, best_move);
+        best
+    }
+    /// Searches captures and promotions until the position is quiet, so that
+    /// positions in the middle of an exchange are not evaluated. The player to
+    /// move may also "stand pat", i.e. not capture, unless in check, in which
+    /// case every evasion is searched.
+    fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.nodes >= self.max_nodes {
+            self.stopped = true;
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(board);
+        }
+
+        let in_check = board.is_check();
+        let (mut moves, mut best) = if in_check {
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                return ply as i32 - MATE;
+            }
+            (moves, -INFINITY)
+        } else {
+            let stand_pat = evaluate(board);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            (board.captures(), stand_pat)
+        };
+        order_moves(board, &mut moves, None);
+
+        for m in moves {
+            // Delta pruning: skip captures that can't raise alpha even with a
+            // margin for the positional gain
+            if !in_check && best + material_gain(board, m) + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            // Captures that lose material are unlikely to be worth it
+            if !in_check && board.see(m) < 0 {
+                continue;
+            }
+            let record = board.make_move_unchecked(m);
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+            board.unmake_move(&record);
+
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+    /// Whether the position is drawn by the fifty move rule, by insufficient
+    /// material, or because it repeats one earlier in the game or the search
+    fn is_draw(&self, board: &Board) -> bool {
+        use crate::Color::*;
+        if board.has_insufficient_material(White) && board.has_insufficient_material(Black) {
+            return true;
+        }
+        // Checkmate takes precedence over the fifty move rule, as in
+        // `Game::compute_state`
+        if board.halfmove_counter() >= 100 {
+            return !board.is_checkmate();
+        }
+        // Positions before the last capture or pawn move can not be repeated
+        let current = self.keys.len() - 1;
+        let since = current.saturating_sub(board.halfmove_counter() as usize);
+        self.keys[since..current].contains(&self.keys[current])
+    }
+}
+
+impl Decider for Engine {
+    /// # Panics
+    /// If there are no legal moves on `board`
+    fn get_move(&mut self, board: &Board) -> Move {
+        self.search(board)
+            .best_move
+            .expect("no legal moves to choose from")
+    }
+}
+
+/// Returns the value of the piece captured by `m` plus what a promotion adds
+fn material_gain(board: &Board, m: Move) -> i32 {
+    let captured = match board[m.to] {
+        Some(piece) => piece.kind.value(),
+        // Pawns that move diagonally to an empty square capture en passant
+        None if m.from.file() != m.to.file() && board[m.from].unwrap().kind == Kind::Pawn => {
+            Kind::Pawn.value()
+        }
+        None => 0,
+    };
+    let promotion = m
+        .promotion
+        .map_or(0, |kind| kind.value() - Kind::Pawn.value());
+    captured + promotion
+}
+
+/// Sorts `moves` so that `first` comes first, then captures and promotions
+/// that don't lose material, by the value of what they win and then by the
+/// least valuable piece, then quiet moves, and last the captures that lose
+/// material according to `Board::see`
+fn order_moves(board: &Board, moves: &mut [Move], first: Option<Move>) {
+    moves.sort_by_cached_key(|&m| {
+        if Some(m) == first {
+            return i32::MIN;
+        }
+        let gain = material_gain(board, m);
+        if gain == 0 {
+            return 0;
+        }
+        let see = board.see(m);
+        if see < 0 {
+            return -see;
+        }
+        let attacker = board[m.from].unwrap().kind;
+        -(100_000 + 10 * gain - attacker.value())
+    });
+}
@@ -0,0 +1,142 @@
+use std::{fmt, mem};
+
+use crate::Move;
+
+use super::{MATE, MAX_PLY};
+
+/// How the stored score relates to the real score of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact
+    Exact,
+    /// The real score is at least the stored score, because the search
+    /// failed high
+    Lower,
+    /// The real score is at most the stored score, because no move raised
+    /// alpha
+    Upper,
+}
+
+/// The result of searching a position, as stored in a `TranspositionTable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    /// The score from the view of the player to move. Mate scores count
+    /// the plies from the root of the search that probed the table, see
+    /// `TranspositionTable::get`.
+    pub score: i32,
+    pub best_move: Option<Move>,
+    /// The search the entry was stored by, see `TranspositionTable::new_search`
+    generation: u8,
+}
+
+/// A fixed size hash table of search results, keyed by `Board::zobrist_key`.
+///
+/// Each key has a single slot. An entry is replaced by one for another
+/// position if it was stored by an earlier search, or if the new one is
+/// searched at least as deep.
+#[derive(Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// Creates a table that takes up about `megabytes` MB. It always has room
+    /// for at least one entry.
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes * 1024 * 1024 / mem::size_of::<Option<Entry>>()).max(1);
+        Self {
+            entries: vec![None; len],
+            generation: 0,
+        }
+    }
+    /// Returns the number of entries that fit in the table
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+    /// Removes every entry, e.g. before a new game
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.generation = 0;
+    }
+    /// Marks the entries stored so far as old, so that they are replaced
+    /// first
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+    /// Returns the entry for `key`, if any. `ply` is how far the position is
+    /// from the root of the search, which mate scores are adjusted to.
+    pub fn get(&self, key: u64, ply: usize) -> Option<Entry> {
+        let entry = self.entries[self.index(key)].filter(|entry| entry.key == key)?;
+        Some(Entry {
+            score: score_from_table(entry.score, ply),
+            ..entry
+        })
+    }
+    /// Stores the result of searching the position with `key` `depth` plies
+    /// deep, `ply` plies from the root
+    pub fn insert(
+        &mut self,
+        key: u64,
+        depth: usize,
+        bound: Bound,
+        score: i32,
+        ply: usize,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(key);
+        let generation = self.generation;
+        let depth = depth.min(u8::MAX as usize) as u8;
+        let best_move = match self.entries[index] {
+            Some(old) if old.key == key => best_move.or(old.best_move),
+            Some(old) if old.generation == generation && old.depth > depth => return,
+            _ => best_move,
+        };
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            bound,
+            score: score_to_table(score, ply),
+            best_move,
+            generation,
+        });
+    }
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+impl fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("capacity", &self.capacity())
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+/// Mate scores count plies from the root of the search. In the table, they
+/// count plies from the stored position instead, since it can be reached at
+/// different distances from the root.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= MAX_PLY as i32 - MATE {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= MAX_PLY as i32 - MATE {
+        score + ply as i32
+    } else {
+        score
+    }
+}
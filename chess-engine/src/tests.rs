@@ -1,5 +1,9 @@
+use std::str::FromStr;
+
 use crate::*;
 
+mod games;
+
 #[test]
 fn arabic_parsing() {
     for (input, output) in [
@@ -18,6 +22,92 @@ fn arabic_parsing_fails() {
     assert!(matches!(Move::arabic("i2a3"), Err(Error::ParsingError),));
     assert!(matches!(Move::arabic("a2u3"), Err(Error::ParsingError),));
     assert!(matches!(Move::arabic("a4a4 "), Err(Error::ParsingError),));
+    assert!(matches!(Move::arabic("e7e8k"), Err(Error::ParsingError),));
+    assert!(matches!(Move::arabic("e7e8qq"), Err(Error::ParsingError),));
+    assert!(matches!(Move::arabic("é7e8"), Err(Error::ParsingError),));
+}
+
+#[test]
+fn arabic_promotion() {
+    let m = Move::arabic("e7e8n").unwrap();
+    assert_eq!(m.promotion, Some(piece::Kind::Knight));
+    assert_eq!(m.as_arabic(), "e7e8n");
+    assert_eq!(Move::arabic("a2a1q").unwrap().as_arabic(), "a2a1q");
+    assert_eq!(Move::arabic("a2a1").unwrap().promotion, None);
+}
+
+#[test]
+fn check_queries() {
+    let pos = |s| Position::from_str(s).unwrap();
+
+    let board = Board::default();
+    assert!(!board.is_check() && !board.is_checkmate() && !board.is_stalemate());
+    assert!(board.checkers().is_empty());
+    assert_eq!(Game::new(board).state(), GameState::Ongoing);
+
+    let board =
+        Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+    assert!(board.is_check() && board.is_checkmate() && !board.is_stalemate());
+    assert_eq!(board.checkers(), vec![pos("h4")]);
+    let mut game = Game::new(board);
+    assert_eq!(
+        game.state(),
+        GameState::Win {
+            winner: Color::Black,
+            reason: WinReason::Checkmate
+        }
+    );
+    assert_eq!(
+        game.make_move(Move::arabic("e1f2").unwrap()),
+        Err(Error::GameOver)
+    );
+
+    let board = Board::from_fen("4r1k1/8/8/8/8/3n4/8/4K3 w - - 0 1").unwrap();
+    assert!(board.is_check() && !board.is_checkmate());
+    assert_eq!(board.checkers(), vec![pos("e8"), pos("d3")]);
+
+    let board = Board::from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+    assert!(!board.is_check() && !board.is_checkmate() && board.is_stalemate());
+    assert_eq!(
+        Game::new(board).state(),
+        GameState::Draw(DrawReason::Stalemate)
+    );
+
+    let board = Board::from_fen("8/8/8/8/8/8/8/K6k w - - 0 1").unwrap();
+    assert_eq!(
+        Game::new(board).state(),
+        GameState::Draw(DrawReason::InsufficientMaterial)
+    );
+}
+
+#[test]
+fn make_move_promotion() {
+    let board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut game = Game::new(board.clone());
+    assert_eq!(
+        Err(Error::MissingPromotion),
+        game.make_move(Move::arabic("b7b8").unwrap())
+    );
+    assert_eq!(
+        Err(Error::InvalidPromotion),
+        game.make_move(Move {
+            promotion: Some(piece::Kind::King),
+            ..Move::arabic("b7b8").unwrap()
+        })
+    );
+    assert_eq!(
+        Err(Error::InvalidPromotion),
+        game.make_move(Move::arabic("e1e2q").unwrap())
+    );
+    assert_eq!(game.board(), &board);
+    assert_eq!(
+        Ok(GameState::Ongoing),
+        game.make_move(Move::arabic("b7b8r").unwrap())
+    );
+    assert_eq!(
+        game.board()[Position::from_str("b8").unwrap()],
+        Some(Piece::new(Color::White, piece::Kind::Rook)),
+    );
 }
 
 #[test]
@@ -44,90 +134,968 @@ fn piece_parsing_fail() {
     }
 }
 
-fn perft(game: Game, depth: usize) -> usize {
-    if depth == 0 {
-        return 1;
+#[test]
+fn legal_moves_default_board() {
+    let moves = Board::default().legal_moves();
+    assert_eq!(20, moves.len());
+    assert!(moves.contains(&Move::arabic("g1f3").unwrap()));
+    assert!(moves.contains(&Move::arabic("e2e4").unwrap()));
+    assert!(!moves.contains(&Move::arabic("e7e5").unwrap()));
+}
+
+#[test]
+fn legal_moves_promotion_and_castling() {
+    let board = Board::from_fen("4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let moves = board.legal_moves();
+    let promotions = moves
+        .iter()
+        .filter(|m| m.from == "b7".parse().unwrap())
+        .map(|m| m.promotion)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        promotions,
+        piece::Kind::PROMOTIONS
+            .iter()
+            .copied()
+            .map(Some)
+            .collect::<Vec<_>>(),
+    );
+    assert!(moves.contains(&Move::arabic("e1g1").unwrap()));
+    assert!(moves.contains(&Move::arabic("e1c1").unwrap()));
+}
+
+#[test]
+fn perft_divide() {
+    let board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+    let moves = perft::divide(&board, 3);
+    assert_eq!(moves.len(), 14);
+    assert_eq!(moves.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2812);
+    assert!(moves.contains(&(Move::arabic("e2e4").unwrap(), 177)));
+    assert!(perft::divide(&board, 0).is_empty());
+}
+
+#[test]
+fn perft_threaded() {
+    let single = perft::Perft::new();
+    let threaded = perft::Perft::new().threads(4);
+    let hashed = perft::Perft::new().threads(3).hash_table(1);
+    for (fen, depth) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            3,
+        ),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        let moves = perft::divide(&board, depth);
+        assert_eq!(single.divide(&board, depth), moves);
+        assert_eq!(threaded.divide(&board, depth), moves);
+        // Twice, the second time with the table filled
+        assert_eq!(hashed.divide(&board, depth), moves);
+        assert_eq!(hashed.perft(&board, depth), perft::perft(&board, depth));
+        assert_eq!(threaded.perft(&board, 0), 1);
+        assert!(threaded.divide(&board, 0).is_empty());
+    }
+}
+
+#[test]
+fn epd_parsing() {
+    let entry = perft::EpdEntry::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - ;D1 26 ;D2 568 ;D3 13744")
+        .unwrap();
+    assert_eq!(entry.board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(entry.nodes, vec![(1, 26), (2, 568), (3, 13744)]);
+
+    let entry = perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 b - - 3 40;D1 5").unwrap();
+    assert_eq!(entry.board.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 3 40");
+    assert_eq!(entry.nodes, vec![(1, 5)]);
+
+    assert!(perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 w - - ;D1").is_err());
+    assert!(perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 w - - ;1 5").is_err());
+    assert!(perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 w - - ;D1 5 6").is_err());
+    assert!(perft::EpdEntry::parse("4k3/8/8/8/8/8/8/4K3 w ;D1 5").is_err());
+}
+
+#[test]
+fn legal_moves_en_passant() {
+    for (fen, m, legal) in [
+        // Both pawns leave the rank, exposing the king to the rook
+        ("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1", "b5c6", false),
+        ("8/8/8/KPp3nr/8/8/8/4k3 w - c6 0 1", "b5c6", true),
+        // Capturing the pawn that gives check
+        ("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", "e4d3", true),
+        // Pinned pawns can only capture along the pin
+        ("8/8/8/1k6/2pP4/8/8/5B1K b - d3 0 1", "c4d3", true),
+        ("8/8/4B3/8/2pP4/8/k7/7K b - d3 0 1", "c4d3", false),
+        // The captured pawn was blocking a bishop
+        ("8/8/2k5/8/3pP3/8/6B1/4K3 b - e3 0 1", "d4e3", false),
+        ("7k/8/8/8/1b1pP3/8/5K2/8 b - e3 0 1", "d4e3", true),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            board.legal_moves().contains(&Move::arabic(m).unwrap()),
+            legal,
+            "at: {}",
+            fen
+        );
     }
-    let mut ans = 0;
-    for rank in 0..8 {
-        for file in 0..8 {
-            let pos = Position::new_unchecked(file, rank);
-            if let Some(piece) = game.board()[pos] {
-                if piece.color != game.board().next_to_move() {
-                    continue;
-                }
-                for to in piece.moves(game.board(), pos) {
-                    let mut g = Game::new(game.board().clone());
-                    g.make_move(Move { from: pos, to }, || panic!()).unwrap();
-                    ans += perft(g, depth - 1);
-                }
-            }
+}
+
+const PERFT_SUITE: &str = include_str!("tests/perft.epd");
+
+/// Checks every position in `PERFT_SUITE` at the depths with at most
+/// `max_nodes` leaf nodes
+fn perft_suite(perft: perft::Perft, max_nodes: u64) {
+    for line in PERFT_SUITE.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = perft::EpdEntry::parse(line).unwrap();
+        for &(depth, nodes) in entry.nodes.iter().filter(|(_, n)| *n <= max_nodes) {
+            assert_eq!(
+                nodes,
+                perft.perft(&entry.board, depth),
+                "at depth {}: {}",
+                depth,
+                line
+            );
         }
     }
-    return ans;
 }
 
 #[test]
-fn perft_1() {
-    let game = Game::new(
-        Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap(),
+fn perft() {
+    perft_suite(perft::Perft::new(), 1_000_000);
+}
+
+#[test]
+#[ignore]
+fn perft_deep() {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    perft_suite(perft::Perft::new().threads(threads), u64::MAX);
+}
+
+#[test]
+fn undo_redo() {
+    let start = "r3k2r/1pp2ppp/8/3pP3/8/8/1p3PPP/R3K2R w KQkq d6 0 10";
+    let mut game = Game::new(Board::from_fen(start).unwrap());
+    let moves = ["e5d6", "c7d6", "e1g1", "b2a1q", "f1a1", "e8c8"];
+    let mut fens = vec![start.to_owned()];
+    for m in moves {
+        game.make_move(Move::arabic(m).unwrap()).unwrap();
+        fens.push(game.board().to_fen());
+    }
+    assert_eq!(game.history().len(), moves.len());
+    assert_eq!(
+        game.history()[0].captured(),
+        Some(Piece::new(Color::Black, piece::Kind::Pawn))
     );
-    assert_eq!(20, perft(game.clone(), 1));
-    assert_eq!(400, perft(game.clone(), 2));
-    assert_eq!(8902, perft(game.clone(), 3));
-    // assert_eq!(197281, perft(game.clone(), 4));
+    assert_eq!(
+        game.history()[3].captured(),
+        Some(Piece::new(Color::White, piece::Kind::Rook))
+    );
+    assert!(game.history()[2].could_castle_kingside(Color::White));
+
+    for (m, fen) in moves.iter().zip(&fens).rev() {
+        assert_eq!(game.undo(), Some(Move::arabic(m).unwrap()));
+        assert_eq!(&game.board().to_fen(), fen);
+    }
+    assert_eq!(game.undo(), None);
+    assert!(game.history().is_empty());
+
+    for fen in &fens[1..] {
+        assert_eq!(game.redo(), Some(GameState::Ongoing));
+        assert_eq!(&game.board().to_fen(), fen);
+    }
+    assert_eq!(game.redo(), None);
+
+    game.undo();
+    game.undo();
+    game.make_move(Move::arabic("g1h1").unwrap()).unwrap();
+    assert_eq!(game.redo(), None);
 }
 
 #[test]
-fn perft_2() {
-    let game = Game::new(
-        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
-            .unwrap(),
+fn undo_redo_after_game_ended() {
+    let e2e4 = Move::arabic("e2e4").unwrap();
+    let mut game = Game::new(Board::default());
+    game.make_move(e2e4).unwrap();
+    game.undo();
+    let resigned = game.resign(Color::White).unwrap();
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.state(), resigned);
+    assert_eq!(game.board(), &Board::default());
+
+    let mut game = Game::new(Board::default());
+    game.make_move(e2e4).unwrap();
+    let resigned = game.resign(Color::Black).unwrap();
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.state(), resigned);
+    assert_eq!(game.history().len(), 1);
+
+    // Checkmate is taken back with the move that caused it
+    let mut game = Game::new(Board::default());
+    for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        game.make_move(Move::arabic(m).unwrap()).unwrap();
+    }
+    assert!(!game.state().is_ongoing());
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.undo(), Some(Move::arabic("d8h4").unwrap()));
+    assert_eq!(game.state(), GameState::Ongoing);
+    assert!(game.redo().is_some());
+    assert!(!game.state().is_ongoing());
+}
+
+#[test]
+fn threefold_and_fivefold_repetition() {
+    let mut game = Game::new(Board::default());
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    let mut states = Vec::new();
+    for _ in 0..4 {
+        for m in shuffle {
+            states.push(game.make_move(Move::arabic(m).unwrap()).unwrap());
+        }
+    }
+    // The starting position occurs for the third time after 8 moves and for
+    // the fifth time after 16
+    assert!(states[..7].iter().all(|&s| s == GameState::Ongoing));
+    assert_eq!(
+        states[7],
+        GameState::DrawClaimable(DrawReason::ThreefoldRepetition)
+    );
+    assert_eq!(states[15], GameState::Draw(DrawReason::FivefoldRepetition));
+    assert_eq!(game.repetitions(), 5);
+    assert_eq!(
+        game.make_move(Move::arabic("g1f3").unwrap()),
+        Err(Error::GameOver)
+    );
+
+    game.undo();
+    game.undo();
+    game.undo();
+    game.undo();
+    assert_eq!(game.repetitions(), 4);
+    assert_eq!(
+        Ok(GameState::Draw(DrawReason::ThreefoldRepetition)),
+        game.claim_draw()
+    );
+    assert_eq!(
+        game.make_move(Move::arabic("g1f3").unwrap()),
+        Err(Error::GameOver)
     );
-    assert_eq!(48, perft(game.clone(), 1));
-    assert_eq!(2039, perft(game.clone(), 2));
-    // assert_eq!(97862, perft(game.clone(), 3));
-    // assert_eq!(4085603, perft(game.clone(), 4));
 }
 
 #[test]
-fn perft_3() {
-    let game = Game::new(Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap());
-    assert_eq!(14, perft(game.clone(), 1));
-    // assert_eq!(191, perft(game.clone(), 2));
-    // assert_eq!(2812, perft(game.clone(), 3));
+fn repetition_ignores_unusable_en_passant() {
+    // After e2e4 an en passant square is set, but no black pawn can use it,
+    // so the position is the same as when the knights return
+    let mut game = Game::new(Board::from_fen("4k1n1/8/8/8/8/8/4P3/4K1N1 w - - 0 1").unwrap());
+    game.make_move(Move::arabic("e2e4").unwrap()).unwrap();
+    for m in ["g8f6", "g1f3", "f6g8", "f3g1"].iter().cycle().take(8) {
+        game.make_move(Move::arabic(m).unwrap()).unwrap();
+    }
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(
+        Err(Error::CannotClaimDraw),
+        Game::new(Board::default()).claim_draw()
+    );
 }
 
 #[test]
-fn perft_4() {
-    let game = Game::new(
-        Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
-            .unwrap(),
+fn fifty_move_rule() {
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/4P3/4K2R w K - 98 70").unwrap());
+    assert_eq!(
+        Ok(GameState::Ongoing),
+        game.make_move(Move::arabic("h1h2").unwrap())
+    );
+    assert_eq!(
+        Ok(GameState::DrawClaimable(DrawReason::FiftyMoveRule)),
+        game.make_move(Move::arabic("e8d8").unwrap())
+    );
+    assert_eq!(
+        Ok(GameState::DrawClaimable(DrawReason::FiftyMoveRule)),
+        game.make_move(Move::arabic("h2h3").unwrap())
+    );
+    assert_eq!(
+        Ok(GameState::Draw(DrawReason::FiftyMoveRule)),
+        game.claim_draw()
+    );
+
+    // A pawn move resets the counter
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/4P3/4K2R w K - 99 70").unwrap());
+    assert_eq!(
+        Ok(GameState::Ongoing),
+        game.make_move(Move::arabic("e2e4").unwrap())
     );
-    assert_eq!(6, perft(game.clone(), 1));
-    // assert_eq!(264, perft(game.clone(), 2));
-    // assert_eq!(9467, perft(game.clone(), 3));
 }
 
-// #[test]
-// fn perft_5() {
-//     let game = Game::new(
-//         Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap(),
-//     );
-//     assert_eq!(44, perft(game.clone(), 1));
-//     // assert_eq!(1486, perft(game.clone(), 2));
-//     // assert_eq!(62379, perft(game.clone(), 3));
-// }
+#[test]
+fn seventy_five_move_rule() {
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/4P3/4K2R b K - 148 90").unwrap());
+    assert_eq!(
+        Ok(GameState::DrawClaimable(DrawReason::FiftyMoveRule)),
+        game.make_move(Move::arabic("e8d8").unwrap())
+    );
+    assert_eq!(
+        Ok(GameState::Draw(DrawReason::SeventyFiveMoveRule)),
+        game.make_move(Move::arabic("h1h2").unwrap())
+    );
+    assert_eq!(
+        Err(Error::GameOver),
+        game.make_move(Move::arabic("d8c8").unwrap())
+    );
+
+    // Checkmate takes precedence
+    let mut game = Game::new(Board::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 149 90").unwrap());
+    assert_eq!(
+        Ok(GameState::Win {
+            winner: Color::White,
+            reason: WinReason::Checkmate,
+        }),
+        game.make_move(Move::arabic("a1a8").unwrap())
+    );
+}
 
 #[test]
-fn perft_6() {
-    let game = Game::new(
-        Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10")
+fn insufficient_material() {
+    for (fen, white, black) in [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true, true),
+        ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true, true),
+        ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", true, true),
+        ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true, true),
+        ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false, false),
+        ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", false, true),
+        ("1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1", false, false),
+        ("3qk3/8/8/8/8/8/8/1N2K3 w - - 0 1", true, false),
+        ("4k3/8/8/8/8/8/4P3/2B1K3 w - - 0 1", false, true),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", false, true),
+        ("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", false, true),
+        ("3qk3/3q4/8/8/8/8/8/1N2K3 w - - 0 1", true, false),
+        ("3rk3/8/8/8/8/8/8/1N2K3 w - - 0 1", false, false),
+        ("3bk3/8/8/8/8/8/8/1N2K3 w - - 0 1", false, false),
+        ("3rk3/8/8/8/8/8/8/2B1K3 w - - 0 1", true, false),
+        ("3qk3/8/8/8/8/8/8/2B1K3 w - - 0 1", true, false),
+        ("1n2k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false, false),
+        ("4k3/4p3/8/8/8/8/8/2B1K3 w - - 0 1", false, false),
+        ("4k3/8/8/8/8/8/8/1BB1K3 w - - 0 1", false, true),
+        ("4k3/8/8/8/8/8/3B4/2B1K3 w - - 0 1", true, true),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            (white, black),
+            (
+                board.has_insufficient_material(Color::White),
+                board.has_insufficient_material(Color::Black)
+            ),
+            "at: {}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn insufficient_material_ends_game() {
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/3r4/2B1K3 w - - 0 1").unwrap());
+    assert_eq!(
+        Ok(GameState::Draw(DrawReason::InsufficientMaterial)),
+        game.make_move(Move::arabic("e1d2").unwrap())
+    );
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/3r4/2B1K2N w - - 0 1").unwrap());
+    assert_eq!(
+        Ok(GameState::Ongoing),
+        game.make_move(Move::arabic("e1d2").unwrap())
+    );
+}
+
+#[test]
+fn game_endings() {
+    let mut game = Game::new(Board::default());
+    assert_eq!("*", GameState::Ongoing.result());
+    let state = game.resign(Color::White).unwrap();
+    assert_eq!(state.winner(), Some(Color::Black));
+    assert_eq!("0-1", state.result());
+    assert_eq!("Black wins by resignation", state.to_string());
+    assert_eq!(Err(Error::GameOver), game.agree_draw());
+
+    let mut game = Game::new(Board::default());
+    assert_eq!("1/2-1/2", game.agree_draw().unwrap().result());
+
+    let mut game = Game::new(Board::default());
+    assert_eq!(
+        Ok(GameState::Win {
+            winner: Color::White,
+            reason: WinReason::Timeout
+        }),
+        game.time_out(Color::Black)
+    );
+
+    let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/Q3K3 w - - 0 1").unwrap());
+    assert_eq!(
+        Ok(GameState::Draw(DrawReason::Timeout)),
+        game.time_out(Color::White)
+    );
+    let mut game = Game::new(Board::default());
+    assert_eq!("1-0", game.adjudicate(Some(Color::White)).unwrap().result());
+
+    // Fool's mate
+    let mut game = Game::new(Board::default());
+    for m in ["f2f3", "e7e5", "g2g4"] {
+        game.make_move(Move::arabic(m).unwrap()).unwrap();
+    }
+    let state = game.make_move(Move::arabic("d8h4").unwrap()).unwrap();
+    assert_eq!("0-1", state.result());
+    assert_eq!("Black wins by checkmate", state.to_string());
+}
+
+fn assert_algebraic(fen: &str, moves: &[(&str, &str)]) {
+    let mut game = Game::new(Board::from_fen(fen).unwrap());
+    for &(arabic, san) in moves {
+        let m = Move::arabic(arabic).unwrap();
+        assert_eq!(
+            m.as_algebraic(game.board()).as_deref(),
+            Some(san),
+            "at: {}",
+            arabic
+        );
+        game.make_move(m).unwrap();
+    }
+}
+
+#[test]
+fn algebraic_notation_games() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_algebraic(start, games::OPERA_GAME);
+    assert_algebraic(start, games::IMMORTAL_GAME);
+    assert_algebraic(start, games::EVERGREEN_GAME);
+}
+
+#[test]
+fn algebraic_notation_special_moves() {
+    for (fen, arabic, san) in [
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "b3d1", "Qb3d1"),
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "b1d1", "Q1d1"),
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "d3d1", "Qdd1"),
+        ("k7/8/8/8/N7/8/N7/7K w - - 0 1", "a2c3", "N2c3"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6"),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", "axb8=Q+"),
+        ("7k/5P2/6K1/8/8/8/8/8 w - - 0 1", "f7f8n", "f8=N"),
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", "O-O+"),
+        ("r3k3/8/8/8/8/8/8/R3K2R b Qq - 0 1", "e8c8", "O-O-O"),
+    ] {
+        assert_algebraic(fen, &[(arabic, san)]);
+    }
+    assert_eq!(
+        None,
+        Move::arabic("e2e5")
+            .unwrap()
+            .as_algebraic(&Board::default())
+    );
+}
+
+#[test]
+fn san_parsing_games() {
+    for moves in [
+        games::OPERA_GAME,
+        games::IMMORTAL_GAME,
+        games::EVERGREEN_GAME,
+    ] {
+        let mut game = Game::new(Board::default());
+        for &(arabic, san) in moves {
+            let m = Move::from_san(san, game.board());
+            assert_eq!(Ok(Move::arabic(arabic).unwrap()), m, "at: {}", san);
+            game.make_move(m.unwrap()).unwrap();
+        }
+    }
+}
+
+#[test]
+fn san_parsing() {
+    for (fen, san, arabic) in [
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "Qb3d1", "b3d1"),
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "Q1d1", "b1d1"),
+        ("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1", "Qdd1", "d3d1"),
+        ("k7/8/8/8/N7/8/N7/7K w - - 0 1", "N2c3", "a2c3"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6", "e5d6"),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q+", "a7b8q"),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8N", "a7a8n"),
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "O-O+", "e1g1"),
+        ("r3k3/8/8/8/8/8/8/R3K2R b Qq - 0 1", "0-0-0", "e8c8"),
+        ("r3k3/8/8/8/8/8/8/R3K2R b Qq - 0 1", "Rxa1+!?", "a8a1"),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            Ok(Move::arabic(arabic).unwrap()),
+            Move::from_san(san, &board),
+            "at: {}",
+            san
+        );
+    }
+}
+
+#[test]
+fn san_parsing_fails() {
+    let board = Board::from_fen("k7/8/8/8/8/1Q1Q4/8/1Q5K w - - 0 1").unwrap();
+    for (san, err) in [
+        ("Qd1", Error::AmbiguousMove),
+        ("Qbd1", Error::AmbiguousMove),
+        ("Qb3e1", Error::IllegalMove),
+        ("Kh3", Error::IllegalMove),
+        ("O-O", Error::IllegalMove),
+        ("Qxd1", Error::IllegalMove),
+        ("Qz1", Error::ParsingError),
+        ("Q", Error::ParsingError),
+        ("", Error::ParsingError),
+        ("Qb3-d1", Error::ParsingError),
+        ("Qd1=", Error::ParsingError),
+        ("Xd1", Error::ParsingError),
+    ] {
+        assert_eq!(Err(err), Move::from_san(san, &board), "at: {}", san);
+    }
+    let board = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(Err(Error::MissingPromotion), Move::from_san("a8", &board));
+    assert_eq!(Err(Error::IllegalMove), Move::from_san("a8=K", &board));
+
+    // After 1.e4 d5, only "exd5" is valid for the capture
+    let board =
+        Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap();
+    for (san, err) in [
+        ("d5", Error::IllegalMove),
+        ("ed5", Error::ParsingError),
+        ("xd5", Error::ParsingError),
+        ("e4xd5", Error::ParsingError),
+        ("ee5", Error::ParsingError),
+        ("Qxh5", Error::IllegalMove),
+        ("Bxc4", Error::IllegalMove),
+    ] {
+        assert_eq!(Err(err), Move::from_san(san, &board), "at: {}", san);
+    }
+    assert_eq!(Move::arabic("e4d5"), Move::from_san("exd5", &board));
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/3rK3 w - - 0 1").unwrap();
+    assert_eq!(Err(Error::IllegalMove), Move::from_san("Kd1", &board));
+    assert_eq!(Err(Error::IllegalMove), Move::from_san("Kxf1", &board));
+    assert_eq!(Move::arabic("e1d1"), Move::from_san("Kxd1", &board));
+}
+
+const OPERA_GAME_PGN: &str = r#"[Event "A Night at the Opera"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8.
+Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14.
+Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+#[test]
+fn pgn_round_trip() {
+    let pgn_game = pgn::PgnGame::from_pgn(OPERA_GAME_PGN).unwrap();
+    assert_eq!(pgn_game.tag("White"), Some("Paul Morphy"));
+    assert_eq!(pgn_game.game.history().len(), games::OPERA_GAME.len());
+    assert_eq!(
+        pgn_game.game.state(),
+        GameState::Win {
+            winner: Color::White,
+            reason: WinReason::Checkmate
+        }
+    );
+    assert_eq!(pgn_game.to_pgn(), OPERA_GAME_PGN);
+}
+
+#[test]
+fn pgn_annotations() {
+    use pgn::{Annotation::*, PgnGame};
+
+    let movetext = |pgn: &str| pgn.split("\n\n").nth(1).unwrap().to_owned();
+    let pgn_game = PgnGame::from_pgn("1. e4 {comment} e5 $1 2. Nf3 1/2-1/2").unwrap();
+    assert_eq!(
+        movetext(&pgn_game.to_pgn()),
+        "1. e4 {comment} 1... e5 $1 2. Nf3 1/2-1/2\n"
+    );
+
+    let pgn =
+        "{Start}  1. e4 e5 (1... c5 {Sicilian}) 2. Nf3!? ; rest of\nNc6 $14 {a\nlong   comment} *";
+    let pgn_game = PgnGame::from_pgn(pgn).unwrap();
+    assert_eq!(
+        pgn_game.annotations,
+        [
+            vec![Comment("Start".to_owned())],
+            vec![],
+            vec![],
+            vec![Nag(5), Comment("rest of".to_owned())],
+            vec![Nag(14), Comment("a\nlong   comment".to_owned())],
+        ]
+    );
+    let exported = pgn_game.to_pgn();
+    assert_eq!(
+        movetext(&exported),
+        "{Start} 1. e4 e5 2. Nf3 $5 {rest of} 2... Nc6 $14 {a long comment} *\n"
+    );
+    let reread = PgnGame::from_pgn(&exported).unwrap();
+    assert_eq!(reread.to_pgn(), exported);
+
+    for pgn in ["1. e4!!! *", "1. e4 $256 *", "1. e4 $ *"] {
+        assert!(PgnGame::from_pgn(pgn).is_err(), "at: {}", pgn);
+    }
+}
+
+#[test]
+fn pgn_reader() {
+    let pgn = r#"
+% An escaped line (1. e4 *
+[Event "First \"quoted\" \\ game"]
+[Result "1/2-1/2"]
+
+1. e4 $1 {A comment
+[spanning] several (lines} e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 ; rest of line 0-1
+Nc6 1/2-1/2
+
+[Event "Second game"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/4K2R b K - 0 40"]
+
+40... Kd7 41. O-O 1-0
+[Event "Third game"]
+1. d4 d5 *"#;
+    let games = pgn::Reader::new(pgn.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(games.len(), 3);
+
+    assert_eq!(games[0].tag("Event"), Some(r#"First "quoted" \ game"#));
+    assert_eq!(games[0].game.history().len(), 4);
+    assert_eq!(
+        games[0].game.state(),
+        GameState::Draw(DrawReason::Adjudication)
+    );
+
+    assert_eq!(
+        games[1].game.board().to_fen(),
+        "8/3k4/8/8/8/8/8/5RK1 b - - 2 41"
+    );
+    assert_eq!(
+        games[1].to_pgn(),
+        r#"[Event "Second game"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "?"]
+[Black "?"]
+[Result "1-0"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/4K2R b K - 0 40"]
+
+40... Kd7 41. O-O 1-0
+"#
+    );
+
+    assert_eq!(games[2].game.state(), GameState::Ongoing);
+    assert_eq!(games[2].game.history().len(), 2);
+}
+
+#[test]
+fn pgn_errors() {
+    use crate::error::PgnError;
+
+    for (pgn, err) in [
+        (
+            "[Event \"?\"]\n\n1. e4 e5 2. Ke3 *",
+            PgnError::Move {
+                line: 3,
+                san: "Ke3".to_owned(),
+            },
+        ),
+        ("1. e4 e5 1-0 2. d4", PgnError::Token { line: 1 }),
+        ("[Event \"?]\n1. e4 *", PgnError::Tag { line: 1 }),
+        ("1. f3 e5 2. g4 Qh4# 1-0", PgnError::Result { line: 1 }),
+        (
+            "[Result \"1-0\"]\n\n1. e4 e5 0-1",
+            PgnError::Result { line: 3 },
+        ),
+        ("1. e4 {e5 *", PgnError::UnterminatedComment),
+        ("1. e4 (1. d4", PgnError::UnterminatedVariation),
+        ("1. e4 (1. d4 *)", PgnError::Token { line: 1 }),
+        ("1. e4 e5) *", PgnError::Token { line: 1 }),
+        ("(1. d4) 1. e4 *", PgnError::Token { line: 1 }),
+        (
+            "1. e4 e5 (1... c5 (1... e6 2. zz) 2. Nf3) 2. Nf3 *",
+            PgnError::Move {
+                line: 1,
+                san: "zz".to_owned(),
+            },
+        ),
+        (
+            "1. e4 e5 2. Nf3\n(2. Nc3 Nc6 (2... Nf6 3. Nxe5)) *",
+            PgnError::Move {
+                line: 2,
+                san: "Nxe5".to_owned(),
+            },
+        ),
+    ] {
+        assert_eq!(
+            Err(Error::PgnError(err)),
+            pgn::PgnGame::from_pgn(pgn).map(|_| ()),
+            "at: {}",
+            pgn
+        );
+    }
+}
+
+#[test]
+fn fen_validation() {
+    use crate::error::FenError::*;
+
+    let pos = |s| Position::from_str(s).unwrap();
+    for (fen, err) in [
+        ("", Pieces),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            RankCount(7),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            RankCount(9),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1",
+            RankTooLong { rank: 7, column: 8 },
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/4P4/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            RankTooLong { rank: 4, column: 2 },
+        ),
+        (
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            InvalidCharacter { rank: 2, column: 0 },
+        ),
+        (
+            "rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            InvalidCharacter { rank: 2, column: 1 },
+        ),
+        (
+            "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            InvalidCharacter { rank: 1, column: 4 },
+        ),
+        (
+            "rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            RankTooShort { rank: 2 },
+        ),
+        (
+            "rnbqkbnr/pppppppp//8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            RankTooShort { rank: 2 },
+        ),
+        ("4k3/8/8/8/8/8/8/8 w - - 0 1", MissingKing(Color::White)),
+        ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", TooManyKings(Color::White)),
+        (
+            "4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1",
+            TooManyPawns(Color::Black),
+        ),
+        (
+            "4k3/8/8/8/8/QQQQQQQQ/QQQQQQQQ/4K3 w - - 0 1",
+            TooManyPieces(Color::White),
+        ),
+        ("4k2P/8/8/8/8/8/8/4K3 w - - 0 1", PawnOnBackRank(pos("h8"))),
+        ("4k3/8/8/8/8/8/8/4K3 x - - 0 1", NextToMove),
+        ("4k3/8/8/8/8/8/8/4K3 w", Castling),
+        ("4k3/8/8/8/8/8/8/4K2R w KK - 0 1", Castling),
+        ("4k3/8/8/8/8/8/8/4K2R w -K - 0 1", Castling),
+        ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", CastlingRight('K')),
+        ("r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1", CastlingRight('k')),
+        ("4k3/8/8/8/8/8/8/R4K1R w Q - 0 1", CastlingRight('Q')),
+        ("4k3/8/8/8/8/8/8/4K3 w - e - 0 1", EnPassant),
+        ("4k3/8/8/8/8/8/8/4K3 w - e9 0 1", EnPassant),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1",
+            EnPassantSquare(pos("d3")),
+        ),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 w - e6 0 1",
+            EnPassantSquare(pos("e6")),
+        ),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1",
+            EnPassantSquare(pos("d6")),
+        ),
+        (
+            "4k3/3n4/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            EnPassantSquare(pos("d6")),
+        ),
+        (
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 7 3",
+            EnPassantSquare(pos("d6")),
+        ),
+        ("4k3/8/8/8/8/8/8/4K3 w - - -1 1", HalfmoveCounter),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0", MoveNumber),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 0", MoveNumber),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1 2", TrailingCharacters),
+        ("4k2R/8/8/8/8/8/8/4K3 w - - 0 1", OpponentInCheck),
+    ] {
+        assert_eq!(
+            Board::from_fen(fen),
+            Err(Error::FenError(err)),
+            "at: {}",
+            fen
+        );
+    }
+
+    for fen in [
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+        "4k3/8/8/8/8/8/8/4K2R b - - 65535 65535",
+    ] {
+        assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn fen_never_panics() {
+    let fen = "r3k2r/pPp2ppp/8/3pP3/8/8/PPPP2PP/R3K2R w KQkq d6 0 1";
+    assert!(Board::from_fen(fen).is_ok());
+    for end in 0..fen.len() {
+        let _ = Board::from_fen(&fen[..end]);
+        for c in "0189/-kKpPwbe ".chars() {
+            let mut changed = fen.to_owned();
+            changed.replace_range(end..end + 1, &c.to_string());
+            if let Ok(board) = Board::from_fen(&changed) {
+                let mut game = Game::new(board);
+                for m in game.legal_moves() {
+                    let _ = game.make_move(m);
+                    game.undo();
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn zobrist_keys() {
+    // Reference keys from the Polyglot book format specification
+    for (moves, key) in [
+        ("", 0x463B96181691FC9C),
+        ("e2e4", 0x823C9B50FD114196),
+        ("e2e4 d7d5", 0x0756B94461C50FB0),
+        ("e2e4 d7d5 e4e5", 0x662FAFB965DB29D4),
+        ("e2e4 d7d5 e4e5 f7f5", 0x22A48B5A8E47FF78),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652A607CA3F242C1),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00FDD303C946BDD9),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3C8123EA7B067637),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a3", 0x5C3F9B829B279560),
+    ] {
+        let mut game = Game::new(Board::default());
+        for m in moves.split_whitespace() {
+            game.make_move(Move::arabic(m).unwrap()).unwrap();
+        }
+        assert_eq!(game.board().polyglot_key(), key, "after: {}", moves);
+        assert_eq!(game.board().zobrist_key(), game.board().zobrist());
+    }
+
+    // The en passant square counts even if no pawn can capture
+    let board = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+    let without_eps = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+    assert_ne!(board.zobrist(), without_eps.zobrist());
+    assert_eq!(board.polyglot_key(), without_eps.polyglot_key());
+}
+
+#[test]
+fn zobrist_incremental() {
+    let mut game = Game::new(
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
             .unwrap(),
     );
-    assert_eq!(46, perft(game.clone(), 1));
-    assert_eq!(2079, perft(game.clone(), 2));
-    // assert_eq!(89890, perft(game.clone(), 3));
+    let initial_key = game.board().zobrist_key();
+    for m in game.legal_moves() {
+        game.make_move(m).unwrap();
+        assert_eq!(game.board().zobrist_key(), game.board().zobrist());
+        for reply in game.legal_moves() {
+            game.make_move(reply).unwrap();
+            assert_eq!(game.board().zobrist_key(), game.board().zobrist());
+            game.undo();
+        }
+        game.undo();
+        assert_eq!(game.board().zobrist_key(), initial_key);
+    }
+}
+
+#[test]
+fn make_unmake_move() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+    ] {
+        let mut board = Board::from_fen(fen).unwrap();
+        let initial = board.clone();
+        for m in initial.legal_moves() {
+            let record = board.make_move(m).unwrap();
+            assert_eq!(record.move_(), m);
+            assert_eq!(board.zobrist_key(), board.zobrist());
+            for reply in board.legal_moves() {
+                let before = board.clone();
+                let record = board.make_move_unchecked(reply);
+                board.unmake_move(&record);
+                assert_eq!(board, before);
+            }
+            board.unmake_move(&record);
+            assert_eq!(board, initial, "after {}", m.as_arabic());
+        }
+    }
+
+    let mut board = Board::default();
+    for (m, err) in [
+        ("e3e4", Error::NoPieceToMove),
+        ("e7e5", Error::OtherPlayersTurn),
+        ("e2e5", Error::IllegalMove),
+        ("e2e4q", Error::InvalidPromotion),
+    ] {
+        assert_eq!(board.make_move(Move::arabic(m).unwrap()), Err(err));
+    }
+    assert_eq!(board, Board::default());
+}
+
+#[test]
+fn magic_attacks() {
+    use bitboard::*;
+
+    // xorshift, to get the same occupancies every time
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for sq in 0..64 {
+        let pos = position(sq);
+        for _ in 0..100 {
+            let occupied = Bitboard(random() & random());
+            assert_eq!(
+                rook_attacks(pos, occupied),
+                classical_rook_attacks(pos, occupied)
+            );
+            assert_eq!(
+                bishop_attacks(pos, occupied),
+                classical_bishop_attacks(pos, occupied)
+            );
+        }
+    }
+}
+
+#[test]
+fn piece_checks() {
+    let board = Board::from_fen("4k3/8/3P4/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let pawn = Piece::new(Color::White, piece::Kind::Pawn);
+    assert!(pawn.checks(Position::from_str("d7").unwrap(), &board));
+    assert!(!pawn.checks(Position::from_str("d6").unwrap(), &board));
 }
 
 #[test]
@@ -139,7 +1107,7 @@ fn board_to_fen() {
     );
     assert_eq!(
         Ok(GameState::Ongoing),
-        game.make_move(Move::arabic("e2e4").unwrap(), || unreachable!())
+        game.make_move(Move::arabic("e2e4").unwrap())
     );
     assert_eq!(
         "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
@@ -147,7 +1115,7 @@ fn board_to_fen() {
     );
     assert_eq!(
         Ok(GameState::Ongoing),
-        game.make_move(Move::arabic("c7c5").unwrap(), || unreachable!())
+        game.make_move(Move::arabic("c7c5").unwrap())
     );
     assert_eq!(
         "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
@@ -155,7 +1123,7 @@ fn board_to_fen() {
     );
     assert_eq!(
         Ok(GameState::Ongoing),
-        game.make_move(Move::arabic("g1f3").unwrap(), || unreachable!())
+        game.make_move(Move::arabic("g1f3").unwrap())
     );
     assert_eq!(
         "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
@@ -258,6 +1226,330 @@ fn default_board() {
             en_passant_square: None,
             halfmove_counter: 0,
             move_number: 1,
+            pieces: [
+                bitboard::Bitboard(0x00FF_0000_0000_FF00),
+                bitboard::Bitboard(0x8100_0000_0000_0081),
+                bitboard::Bitboard(0x4200_0000_0000_0042),
+                bitboard::Bitboard(0x2400_0000_0000_0024),
+                bitboard::Bitboard(0x0800_0000_0000_0008),
+                bitboard::Bitboard(0x1000_0000_0000_0010),
+            ],
+            colors: [
+                bitboard::Bitboard(0xFFFF_0000_0000_0000),
+                bitboard::Bitboard(0x0000_0000_0000_FFFF),
+            ],
+            zobrist_key: 0x463B96181691FC9C,
         }
     );
 }
+
+#[test]
+fn search_finds_mates() {
+    use search::{Engine, Limit, MATE};
+
+    // Two rook mate in two moves
+    let board = Board::from_fen("7k/8/8/8/8/8/R7/1R5K w - - 0 1").unwrap();
+    let result = Engine::new(Limit::Depth(5)).search(&board);
+    assert_eq!(result.score, MATE - 3);
+    assert_eq!(result.depth, 3);
+    assert_eq!(result.pv.len(), 3);
+    let mut game = Game::new(board);
+    for &m in &result.pv {
+        game.make_move(m).unwrap();
+    }
+    assert!(game.board().is_checkmate());
+
+    // Being mated in one move
+    let board = Board::from_fen("7k/R7/8/8/8/8/8/1R4K1 b - - 0 1").unwrap();
+    let result = Engine::new(Limit::Depth(3)).search(&board);
+    assert_eq!(result.score, 2 - MATE);
+
+    let board = Board::from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+    let result = Engine::new(Limit::Depth(3)).search(&board);
+    assert_eq!((result.best_move, result.score), (None, 0));
+}
+
+#[test]
+fn search_limits() {
+    use search::{Engine, Limit};
+
+    // Winning the queen, which can't be defended
+    let board = Board::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+    let mut engine = Engine::new(Limit::Depth(4));
+    let result = engine.search(&board);
+    assert_eq!(result.best_move, Some(Move::arabic("d2d5").unwrap()));
+    assert_eq!(result.depth, 4);
+    assert_eq!(engine.get_move(&board), Move::arabic("d2d5").unwrap());
+
+    // A search that is cut short is not used, but the first one always is
+    engine.set_limit(Limit::Nodes(1));
+    let result = engine.search(&Board::default());
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move.is_some());
+    engine.set_limit(Limit::Nodes(20_000));
+    let result = engine.search(&Board::default());
+    assert!(result.depth > 1);
+    assert!(result.nodes <= 20_000);
+
+    // The principal variation is a line of legal moves
+    let mut board =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let result = Engine::new(Limit::Depth(3)).search(&board);
+    assert_eq!(result.pv.len(), 3);
+    for m in result.pv {
+        board.make_move(m).unwrap();
+    }
+}
+
+#[test]
+fn search_draws() {
+    use search::{Engine, Limit, MATE};
+
+    // Black is lost, but can go back to the position the game started from
+    let mut game = Game::new(Board::from_fen("k7/8/8/8/8/8/8/3RR2K w - - 0 1").unwrap());
+    for m in ["h1g1", "a8b8", "g1h1"] {
+        game.make_move(Move::arabic(m).unwrap()).unwrap();
+    }
+    let mut engine = Engine::new(Limit::Depth(1));
+    let result = engine.search_game(&game);
+    assert_eq!(result.best_move, Some(Move::arabic("b8a8").unwrap()));
+    assert_eq!(result.score, 0);
+    assert!(engine.search(game.board()).score < -500);
+
+    // Checkmate on the move that reaches the fifty move limit still counts
+    let board = Board::from_fen("7k/R7/6K1/8/8/8/8/8 w - - 99 80").unwrap();
+    let result = engine.search(&board);
+    assert_eq!(result.best_move, Some(Move::arabic("a7a8").unwrap()));
+    assert_eq!(result.score, MATE - 1);
+}
+
+/// Returns the FEN of the position with the board flipped and the colors
+/// swapped
+fn mirror_fen(fen: &str) -> String {
+    let fields = fen.split(' ').collect::<Vec<_>>();
+    let swap_case = |s: &str| {
+        s.chars()
+            .map(|c| match c {
+                'a'..='z' => c.to_ascii_uppercase(),
+                _ => c.to_ascii_lowercase(),
+            })
+            .collect::<String>()
+    };
+    let pieces = fields[0].split('/').rev().collect::<Vec<_>>().join("/");
+    let next_to_move = if fields[1] == "w" { "b" } else { "w" };
+    let en_passant = fields[3]
+        .replace('3', "x")
+        .replace('6', "3")
+        .replace('x', "6");
+    format!(
+        "{} {} {} {} {} {}",
+        swap_case(&pieces),
+        next_to_move,
+        swap_case(fields[2]),
+        en_passant,
+        fields[4],
+        fields[5]
+    )
+}
+
+#[test]
+fn eval_symmetry() {
+    for line in PERFT_SUITE.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fen = perft::EpdEntry::parse(line).unwrap().board.to_fen();
+        let board = Board::from_fen(&fen).unwrap();
+        let mirrored = Board::from_fen(&mirror_fen(&fen)).unwrap();
+        assert_eq!(
+            eval::breakdown(&board),
+            eval::breakdown(&mirrored),
+            "at: {}",
+            fen
+        );
+        assert_eq!(eval::evaluate(&board), eval::breakdown(&board).total());
+    }
+    assert_eq!(eval::evaluate(&Board::default()), 0);
+    assert_eq!(eval::breakdown(&Board::default()).phase, 24);
+}
+
+#[test]
+fn eval_terms() {
+    let breakdown = |fen| eval::breakdown(&Board::from_fen(fen).unwrap());
+
+    // Doubled and isolated pawns against healthy ones, with no passed pawns
+    let pawns = breakdown("4k3/pp3ppp/8/8/8/2P5/2P2PPP/4K3 w - - 0 1");
+    assert!(pawns.pawn_structure < 0);
+    assert_eq!(pawns.material, 0);
+    // A passed pawn is worth more the further it has advanced
+    let passed = |fen| breakdown(fen).pawn_structure;
+    assert!(passed("4k3/8/8/8/8/8/PP6/4K3 w - - 0 1") > 0);
+    assert!(passed("4k3/PP6/8/8/8/8/8/4K3 w - - 0 1") > passed("4k3/8/8/8/8/8/PP6/4K3 w - - 0 1"));
+    assert!(passed("4k3/8/8/8/8/8/PP6/4K3 b - - 0 1") < 0);
+
+    // A king without pawns in front of it and a queen next to it
+    let king = breakdown("6k1/5ppp/8/8/8/8/8/5qK1 w - - 0 1");
+    assert!(king.king_safety < 0);
+    assert_eq!(king.phase, 4);
+
+    // A knight in the corner has fewer squares than one in the center
+    let mobility = breakdown("4k3/8/8/8/3N4/8/8/n3K3 w - - 0 1");
+    assert!(mobility.mobility > 0);
+    assert!(mobility.piece_squares > 0);
+}
+
+#[test]
+fn transposition_table() {
+    use search::{Bound, TranspositionTable, MATE};
+
+    let mut table = TranspositionTable::new(1);
+    let capacity = table.capacity() as u64;
+    assert!(capacity > 1000);
+    let m = Move::arabic("e2e4").unwrap();
+
+    table.insert(7, 4, Bound::Exact, 25, 2, Some(m));
+    let entry = table.get(7, 5).unwrap();
+    assert_eq!(
+        (entry.depth, entry.bound, entry.score, entry.best_move),
+        (4, Bound::Exact, 25, Some(m))
+    );
+    assert_eq!(table.get(8, 0), None);
+    // Keys that share a slot
+    assert_eq!(table.get(7 + capacity, 0), None);
+
+    // Mate scores are stored relative to the position
+    table.insert(9, 3, Bound::Lower, MATE - 5, 3, None);
+    assert_eq!(table.get(9, 1).unwrap().score, MATE - 3);
+    table.insert(10, 3, Bound::Upper, 6 - MATE, 4, None);
+    assert_eq!(table.get(10, 0).unwrap().score, 2 - MATE);
+
+    // A shallower search of another position doesn't replace a deeper one,
+    // unless the deeper one is from an earlier search
+    table.insert(7 + capacity, 3, Bound::Exact, 0, 0, None);
+    assert!(table.get(7, 0).is_some());
+    table.new_search();
+    table.insert(7 + capacity, 3, Bound::Exact, 0, 0, None);
+    assert!(table.get(7, 0).is_none());
+    assert!(table.get(7 + capacity, 0).is_some());
+    // The same position is always replaced, keeping the best move if the new
+    // search didn't find one
+    table.insert(9, 1, Bound::Upper, -40, 0, None);
+    table.insert(9, 1, Bound::Lower, 50, 0, Some(m));
+    table.insert(9, 1, Bound::Upper, 40, 0, None);
+    let entry = table.get(9, 0).unwrap();
+    assert_eq!(
+        (entry.bound, entry.score, entry.best_move),
+        (Bound::Upper, 40, Some(m))
+    );
+
+    table.clear();
+    assert_eq!(table.get(9, 0), None);
+}
+
+#[test]
+fn search_reuses_table() {
+    use search::{Engine, Limit};
+
+    let board =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let mut engine = Engine::new(Limit::Depth(4));
+    let first = engine.search(&board);
+    let second = engine.search(&board);
+    assert!(second.nodes < first.nodes / 2);
+    assert!(second.best_move.is_some());
+
+    engine.new_game();
+    assert_eq!(engine.search(&board).nodes, first.nodes);
+}
+
+#[test]
+fn captures() {
+    for line in PERFT_SUITE.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut board = perft::EpdEntry::parse(line).unwrap().board;
+        for m in board.legal_moves() {
+            let record = board.make_move_unchecked(m);
+            let expected = board
+                .legal_moves()
+                .into_iter()
+                .filter(|m| {
+                    let record = board.clone().make_move_unchecked(*m);
+                    record.captured().is_some() || m.promotion.is_some()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(board.captures(), expected, "at: {}", board.to_fen());
+            for pos in board.color_bitboard(board.next_to_move()) {
+                let piece = board[pos].unwrap();
+                assert!(piece
+                    .captures(&board, pos)
+                    .all(|to| expected.iter().any(|m| m.from == pos && m.to == to)));
+            }
+            board.unmake_move(&record);
+        }
+    }
+}
+
+#[test]
+fn search_quiescence() {
+    use search::{Engine, Limit};
+
+    // Taking the pawn on e5 loses the queen, which a one ply search only
+    // sees by searching the captures after it
+    let board = Board::from_fen("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1").unwrap();
+    let result = Engine::new(Limit::Depth(1)).search(&board);
+    assert_ne!(result.best_move, Some(Move::arabic("e1e5").unwrap()));
+    assert!(result.score > 500);
+
+    // Mates right at the horizon are seen
+    let board = Board::from_fen("6k1/5ppp/8/8/8/8/r4PPP/6K1 b - - 0 1").unwrap();
+    let result = Engine::new(Limit::Depth(1)).search(&board);
+    assert_eq!(result.best_move, Some(Move::arabic("a2a1").unwrap()));
+    assert_eq!(result.score, search::MATE - 1);
+}
+
+#[test]
+fn static_exchange_evaluation() {
+    for (fen, m, value) in [
+        // Undefended pawn
+        (
+            "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+            "e1e5",
+            100,
+        ),
+        // Knight for pawn, with pieces joining from behind on both sides
+        (
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "d3e5",
+            -220,
+        ),
+        // A rook behind a rook wins the pawn
+        ("4k3/4r3/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5", 100),
+        ("4k3/4r3/8/4p3/8/8/4R3/6K1 w - - 0 1", "e2e5", -400),
+        // Defended by a pawn
+        ("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1", "e1e5", -800),
+        // Equal trade
+        ("4k3/8/3r4/8/8/8/3R4/4K3 w - - 0 1", "d2d6", 500),
+        ("4k3/3r4/3r4/8/8/8/3R4/4K3 w - - 0 1", "d2d6", 0),
+        // En passant
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100),
+        // Promotions
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", 1300),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q", 800 - 900),
+        // A quiet move to an attacked square
+        ("7k/8/8/3p4/8/8/8/4RK2 w - - 0 1", "e1e4", -500),
+        ("7k/8/8/8/8/8/8/4RK2 w - - 0 1", "e1e4", 0),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            board.see(Move::arabic(m).unwrap()),
+            value,
+            "{} at: {}",
+            m,
+            fen
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Famous games, as pairs of moves in arabic notation and SAN
+
+pub const OPERA_GAME: &[(&str, &str)] = &[
+    ("e2e4", "e4"),
+    ("e7e5", "e5"),
+    ("g1f3", "Nf3"),
+    ("d7d6", "d6"),
+    ("d2d4", "d4"),
+    ("c8g4", "Bg4"),
+    ("d4e5", "dxe5"),
+    ("g4f3", "Bxf3"),
+    ("d1f3", "Qxf3"),
+    ("d6e5", "dxe5"),
+    ("f1c4", "Bc4"),
+    ("g8f6", "Nf6"),
+    ("f3b3", "Qb3"),
+    ("d8e7", "Qe7"),
+    ("b1c3", "Nc3"),
+    ("c7c6", "c6"),
+    ("c1g5", "Bg5"),
+    ("b7b5", "b5"),
+    ("c3b5", "Nxb5"),
+    ("c6b5", "cxb5"),
+    ("c4b5", "Bxb5+"),
+    ("b8d7", "Nbd7"),
+    ("e1c1", "O-O-O"),
+    ("a8d8", "Rd8"),
+    ("d1d7", "Rxd7"),
+    ("d8d7", "Rxd7"),
+    ("h1d1", "Rd1"),
+    ("e7e6", "Qe6"),
+    ("b5d7", "Bxd7+"),
+    ("f6d7", "Nxd7"),
+    ("b3b8", "Qb8+"),
+    ("d7b8", "Nxb8"),
+    ("d1d8", "Rd8#"),
+];
+
+pub const IMMORTAL_GAME: &[(&str, &str)] = &[
+    ("e2e4", "e4"),
+    ("e7e5", "e5"),
+    ("f2f4", "f4"),
+    ("e5f4", "exf4"),
+    ("f1c4", "Bc4"),
+    ("d8h4", "Qh4+"),
+    ("e1f1", "Kf1"),
+    ("b7b5", "b5"),
+    ("c4b5", "Bxb5"),
+    ("g8f6", "Nf6"),
+    ("g1f3", "Nf3"),
+    ("h4h6", "Qh6"),
+    ("d2d3", "d3"),
+    ("f6h5", "Nh5"),
+    ("f3h4", "Nh4"),
+    ("h6g5", "Qg5"),
+    ("h4f5", "Nf5"),
+    ("c7c6", "c6"),
+    ("g2g4", "g4"),
+    ("h5f6", "Nf6"),
+    ("h1g1", "Rg1"),
+    ("c6b5", "cxb5"),
+    ("h2h4", "h4"),
+    ("g5g6", "Qg6"),
+    ("h4h5", "h5"),
+    ("g6g5", "Qg5"),
+    ("d1f3", "Qf3"),
+    ("f6g8", "Ng8"),
+    ("c1f4", "Bxf4"),
+    ("g5f6", "Qf6"),
+    ("b1c3", "Nc3"),
+    ("f8c5", "Bc5"),
+    ("c3d5", "Nd5"),
+    ("f6b2", "Qxb2"),
+    ("f4d6", "Bd6"),
+    ("c5g1", "Bxg1"),
+    ("e4e5", "e5"),
+    ("b2a1", "Qxa1+"),
+    ("f1e2", "Ke2"),
+    ("b8a6", "Na6"),
+    ("f5g7", "Nxg7+"),
+    ("e8d8", "Kd8"),
+    ("f3f6", "Qf6+"),
+    ("g8f6", "Nxf6"),
+    ("d6e7", "Be7#"),
+];
+
+pub const EVERGREEN_GAME: &[(&str, &str)] = &[
+    ("e2e4", "e4"),
+    ("e7e5", "e5"),
+    ("g1f3", "Nf3"),
+    ("b8c6", "Nc6"),
+    ("f1c4", "Bc4"),
+    ("f8c5", "Bc5"),
+    ("b2b4", "b4"),
+    ("c5b4", "Bxb4"),
+    ("c2c3", "c3"),
+    ("b4a5", "Ba5"),
+    ("d2d4", "d4"),
+    ("e5d4", "exd4"),
+    ("e1g1", "O-O"),
+    ("d4d3", "d3"),
+    ("d1b3", "Qb3"),
+    ("d8f6", "Qf6"),
+    ("e4e5", "e5"),
+    ("f6g6", "Qg6"),
+    ("f1e1", "Re1"),
+    ("g8e7", "Nge7"),
+    ("c1a3", "Ba3"),
+    ("b7b5", "b5"),
+    ("b3b5", "Qxb5"),
+    ("a8b8", "Rb8"),
+    ("b5a4", "Qa4"),
+    ("a5b6", "Bb6"),
+    ("b1d2", "Nbd2"),
+    ("c8b7", "Bb7"),
+    ("d2e4", "Ne4"),
+    ("g6f5", "Qf5"),
+    ("c4d3", "Bxd3"),
+    ("f5h5", "Qh5"),
+    ("e4f6", "Nf6+"),
+    ("g7f6", "gxf6"),
+    ("e5f6", "exf6"),
+    ("h8g8", "Rg8"),
+    ("a1d1", "Rad1"),
+    ("h5f3", "Qxf3"),
+    ("e1e7", "Rxe7+"),
+    ("c6e7", "Nxe7"),
+    ("a4d7", "Qxd7+"),
+    ("e8d7", "Kxd7"),
+    ("d3f5", "Bf5+"),
+    ("d7e8", "Ke8"),
+    ("f5d7", "Bd7+"),
+    ("e8f8", "Kf8"),
+    ("a3e7", "Bxe7#"),
+];
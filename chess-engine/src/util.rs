@@ -1,102 +1,64 @@
 use std::{fmt, str::FromStr};
 
-use crate::{piece, Board, Error};
+use crate::{piece, Error};
+
+mod san;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
+    /// The kind of piece a pawn is promoted to. Only set for moves where a
+    /// pawn reaches the last rank.
+    pub promotion: Option<piece::Kind>,
 }
 
 impl From<(Position, Position)> for Move {
     fn from((from, to): (Position, Position)) -> Self {
-        Self { from, to }
+        Self::new(from, to)
     }
 }
 
 impl Move {
+    pub fn new(from: Position, to: Position) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+        }
+    }
     /// Returns the move represented by `s` in arabic notation, e.g. "a4c6".
+    /// Promotions are written UCI-style with the kind of the new piece
+    /// appended, e.g. "e7e8q".
     ///
     /// If `s` is not valid arabic notation, `Err(Error::ParsingError)` is returned.
     pub fn arabic(s: &str) -> Result<Self, Error> {
-        match s.len() {
-            4 => Ok(Self {
-                from: s[..2].parse()?,
-                to: s[2..4].parse()?,
-            }),
-            5.. => Err(Error::ParsingError),
-            0..=3 => Err(Error::ParsingError),
-            _ => unreachable!(),
-        }
+        let (from, to) = match (s.get(..2), s.get(2..4)) {
+            (Some(from), Some(to)) => (from.parse()?, to.parse()?),
+            _ => return Err(Error::ParsingError),
+        };
+        let promotion = match &s[4..] {
+            "" => None,
+            "q" | "r" | "b" | "n" => Some(piece::Kind::from_name(s.as_bytes()[4] as char)?),
+            _ => return Err(Error::ParsingError),
+        };
+        Ok(Self {
+            from,
+            to,
+            promotion,
+        })
     }
-    /// Returns the move as a string in arabic notation, e.g. "h8a1"
+    /// Returns the move as a string in arabic notation, e.g. "h8a1" or "a2a1q"
     pub fn as_arabic(&self) -> String {
-        format!("{}{}", self.from, self.to)
-    }
-    /// # Note
-    /// Doesn't work
-    /// # If it worked:
-    /// Returns the move as a string in algebraic notation, e.g. "Qh4xe1"
-    /// `board` must be the state of the board *before* the move is made.
-    ///
-    /// For moves that lead to pawn promotions, `promotion` must be set to the
-    /// kind of piece the pawn was turned in to. If not, None will be returned.
-    /// For all other moves, it is not used and should be set to None.
-    ///
-    /// If no piece exists on the `move`'s `from` tile, None is returned.
-    pub fn as_algebraic(&self, board: &Board, promotion: Option<piece::Kind>) -> Option<String> {
-        let piece = board[self.from]?;
-
-        if piece.kind == piece::Kind::King && self.to.file() == self.from.file() + 2 {
-            // Kingside castling
-            return Some("0-0".to_owned());
-        } else if piece.kind == piece::Kind::King && self.to.file() == self.to.file() - 2 {
-            // Queenside castling
-            return Some("0-0-0".to_owned());
+        match self.promotion {
+            Some(kind) => format!(
+                "{}{}{}",
+                self.from,
+                self.to,
+                kind.name().to_ascii_lowercase()
+            ),
+            None => format!("{}{}", self.from, self.to),
         }
-
-        let kind_part = if piece.kind == piece::Kind::Pawn {
-            "".to_owned()
-        } else {
-            piece.kind.name().to_string()
-        };
-        let from_file_part = if (0..8u8)
-            .map(|rank| Position::new_unchecked(self.from.file(), rank))
-            .map(|pos| board[pos] == Some(piece))
-            .any(|b| b)
-        {
-            ((self.from.file() + b'a') as char).to_string()
-        } else {
-            "".to_owned()
-        };
-        let from_rank_part = if (0..8u8)
-            .map(|file| Position::new_unchecked(file, self.from.rank()))
-            .map(|pos| board[pos] == Some(piece))
-            .any(|b| b)
-        {
-            ((b'8' - self.from.rank()) as char).to_string()
-        } else {
-            "".to_owned()
-        };
-        let capture_part = if board[self.to].is_some() { "x" } else { "" };
-        let dest_part = self.to;
-        let promotion_part = promotion.map_or("".to_owned(), |kind| kind.name().to_string());
-        let check_part = if piece.checks(self.to, board) {
-            "+"
-        } else {
-            ""
-        };
-
-        Some(format!(
-            "{kind}{from_file}{from_rank}{capture}{destination}{promotion}{check}",
-            kind = kind_part,
-            from_file = from_file_part,
-            from_rank = from_rank_part,
-            capture = capture_part,
-            destination = dest_part,
-            promotion = promotion_part,
-            check = check_part,
-        ))
     }
 }
 
@@ -139,7 +101,7 @@ impl Position {
         }
     }
     pub fn new_i8(file: i8, rank: i8) -> Option<Self> {
-        if 0 <= file && file < 8 && 0 <= rank && rank < 8 {
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
             Some(Self {
                 file: file as u8,
                 rank: rank as u8,
@@ -178,12 +140,12 @@ impl FromStr for Position {
         if s.len() > 2 {
             return Err(Error::ParsingError);
         }
-        let file = match s.get(0) {
+        let file = match s.first() {
             c @ Some(b'a'..=b'h') => c.unwrap() - b'a',
             _ => return Err(Error::ParsingError),
         };
-        let rank = match s[1] {
-            c @ b'1'..=b'8' => b'8' - c,
+        let rank = match s.get(1) {
+            Some(c @ b'1'..=b'8') => b'8' - c,
             _ => return Err(Error::ParsingError),
         };
         Ok(Self { file, rank })
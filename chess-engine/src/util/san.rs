@@ -0,0 +1,168 @@
+use crate::{piece, Board, Error, Move, Position};
+
+impl Move {
+    /// Returns the move as a string in standard algebraic notation (SAN),
+    /// e.g. "Nbd7", "exd6", "O-O-O" or "e8=Q+".
+    ///
+    /// `board` must be the state of the board *before* the move is made. If
+    /// the move is not legal on `board`, `None` is returned.
+    pub fn as_algebraic(&self, board: &Board) -> Option<String> {
+        let legal_moves = board.legal_moves();
+        if !legal_moves.contains(self) {
+            return None;
+        }
+        let piece = board[self.from]?;
+        let delta_file = self.to.file() as i8 - self.from.file() as i8;
+
+        let mut san = String::new();
+        if piece.kind == piece::Kind::King && delta_file == 2 {
+            san.push_str("O-O");
+        } else if piece.kind == piece::Kind::King && delta_file == -2 {
+            san.push_str("O-O-O");
+        } else {
+            let captures = board[self.to].is_some()
+                || piece.kind == piece::Kind::Pawn && board.en_passant_square() == Some(self.to);
+
+            if piece.kind == piece::Kind::Pawn {
+                if captures {
+                    san.push(file_name(self.from.file()));
+                }
+            } else {
+                san.push(piece.kind.name());
+
+                // Other pieces of the same kind that can move to the same
+                // square must be told apart from this one
+                let others = legal_moves
+                    .iter()
+                    .filter(|m| m.to == self.to && m.from != self.from)
+                    .filter(|m| board[m.from] == Some(piece))
+                    .map(|m| m.from)
+                    .collect::<Vec<_>>();
+                if !others.is_empty() {
+                    let same_file = others.iter().any(|p| p.file() == self.from.file());
+                    let same_rank = others.iter().any(|p| p.rank() == self.from.rank());
+                    if !same_file {
+                        san.push(file_name(self.from.file()));
+                    } else if !same_rank {
+                        san.push(rank_name(self.from.rank()));
+                    } else {
+                        san.push(file_name(self.from.file()));
+                        san.push(rank_name(self.from.rank()));
+                    }
+                }
+            }
+
+            if captures {
+                san.push('x');
+            }
+            san.push_str(&self.to.to_string());
+            if let Some(kind) = self.promotion {
+                san.push('=');
+                san.push(kind.name());
+            }
+        }
+
+        let mut after = board.clone();
+        after.make_move_unchecked(*self);
+        if after.is_check() {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        Some(san)
+    }
+    /// Returns the move represented by `s` in standard algebraic notation
+    /// (SAN), e.g. "Nbd7", "exd6", "O-O-O" or "e8=Q+", on `board`.
+    ///
+    /// Check and mate markers as well as annotations such as "!?" are
+    /// allowed but not verified. Returns `Err(Error::ParsingError)` if `s` is
+    /// not valid SAN, `Err(Error::IllegalMove)` if no legal move matches and
+    /// `Err(Error::AmbiguousMove)` if several do. Captures must be marked with
+    /// "x" and other moves must not be.
+    pub fn from_san(s: &str, board: &Board) -> Result<Self, Error> {
+        let s = s.trim_end_matches(['+', '#', '!', '?']);
+        if !s.is_ascii() || s.is_empty() {
+            return Err(Error::ParsingError);
+        }
+        let legal_moves = board.legal_moves();
+
+        if let Some(delta_file) = match s {
+            "O-O" | "0-0" => Some(2),
+            "O-O-O" | "0-0-0" => Some(-2),
+            _ => None,
+        } {
+            return legal_moves
+                .into_iter()
+                .find(|m| {
+                    board[m.from].map(|p| p.kind) == Some(piece::Kind::King)
+                        && m.to.file() as i8 - m.from.file() as i8 == delta_file
+                })
+                .ok_or(Error::IllegalMove);
+        }
+
+        let (kind, mut rest) = match s.as_bytes()[0] {
+            c @ (b'N' | b'B' | b'R' | b'Q' | b'K') => (piece::Kind::from_name(c as char)?, &s[1..]),
+            _ => (piece::Kind::Pawn, s),
+        };
+
+        let mut promotion = None;
+        if let Some(last) = rest.chars().last().filter(char::is_ascii_uppercase) {
+            promotion = Some(piece::Kind::from_name(last)?);
+            rest = rest[..rest.len() - 1].trim_end_matches('=');
+        }
+
+        if rest.len() < 2 {
+            return Err(Error::ParsingError);
+        }
+        let to: Position = rest[rest.len() - 2..].parse()?;
+        let mut disambiguation = &rest[..rest.len() - 2];
+        let captures = disambiguation.ends_with('x');
+        if captures {
+            disambiguation = &disambiguation[..disambiguation.len() - 1];
+        }
+        let (from_file, from_rank) = match disambiguation.as_bytes() {
+            [] => (None, None),
+            [f @ b'a'..=b'h'] => (Some(f - b'a'), None),
+            [r @ b'1'..=b'8'] => (None, Some(b'8' - r)),
+            [f @ b'a'..=b'h', r @ b'1'..=b'8'] => (Some(f - b'a'), Some(b'8' - r)),
+            _ => return Err(Error::ParsingError),
+        };
+
+        let is_capture = board[to].is_some()
+            || kind == piece::Kind::Pawn && board.en_passant_square() == Some(to);
+        // Pawn moves name the file they come from exactly when they capture
+        if kind == piece::Kind::Pawn && (from_rank.is_some() || from_file.is_some() != captures) {
+            return Err(Error::ParsingError);
+        }
+        if captures != is_capture {
+            return Err(Error::IllegalMove);
+        }
+
+        let mut candidates = legal_moves.into_iter().filter(|m| {
+            m.to == to
+                && board[m.from].map(|p| p.kind) == Some(kind)
+                && from_file.is_none_or(|f| m.from.file() == f)
+                && from_rank.is_none_or(|r| m.from.rank() == r)
+                && (m.promotion == promotion || m.promotion.is_some() && promotion.is_none())
+        });
+        let m = candidates.next().ok_or(Error::IllegalMove)?;
+        if m.promotion != promotion {
+            return Err(Error::MissingPromotion);
+        }
+        if candidates.next().is_some() {
+            return Err(Error::AmbiguousMove);
+        }
+        Ok(m)
+    }
+}
+
+fn file_name(file: u8) -> char {
+    (b'a' + file) as char
+}
+
+fn rank_name(rank: u8) -> char {
+    (b'8' - rank) as char
+}
@@ -1,5 +1,5 @@
 #![allow(unused)]
-use std::{io::BufRead, str::FromStr, time};
+use std::{io::BufRead, str::FromStr, thread, time};
 
 use chess_engine::{
     self,
@@ -13,7 +13,7 @@ use chess_engine::{
     Board,
     GameState,
     Move,
-    
+    search::{Engine, Limit},
 };
 
 use bevy::{
@@ -71,7 +71,14 @@ struct Game {
     game: chess_engine::Game,
     highlighted_piece: Option<PieceHighlight>,
     is_promoting: bool,
-    promotion_pos: Option<Position>,
+    pending_promotion: Option<Move>,
+    /// The player the computer makes the moves for, if any
+    computer: Option<Color>,
+    /// `None` while the computer searches for a move
+    engine: Option<Engine>,
+    /// The search for the computer's move, running on another thread so that
+    /// the window does not freeze, and the board it searches
+    search: Option<(Board, thread::JoinHandle<(Engine, Move)>)>,
 }
 
 
@@ -124,6 +131,8 @@ fn main() {
             SystemSet::new()
                 .with_run_criteria(run_main.system())
                 .with_system(handle_mouse_clicks_system.system())
+                .with_system(handle_takeback_system.system())
+                .with_system(handle_computer_system.system())
                 .with_system(pieces_despawn_system.system())
                 .with_system(pieces_spawn.system())
                 .with_system(promotion_options_despawn_system.system())
@@ -174,13 +183,12 @@ fn handle_mouse_click_for_promotion(
 }
 
 fn exchange_promotion_piece(mut game: ResMut<Game>, kind: Kind) {
-    let pos = game.promotion_pos;
-    let piece = Piece{
-        color: get_other_color(game.game.clone().board().next_to_move()),
-        kind
+    let m = Move {
+        promotion: Some(kind),
+        ..game.pending_promotion.take().unwrap()
     };
-    game.game.unsafe_set_piece(pos.unwrap(), piece);
     game.is_promoting = false;
+    make_move(&mut game, m);
 }
 
     
@@ -206,10 +214,10 @@ fn dummy_system(
     game: Res<Game>,    
     materials: Res<Materials>,
 ){
-    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Queen, get_other_color(game.game.board().next_to_move()), &materials), -150., 150.);
-    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Rook, get_other_color(game.game.board().next_to_move()), &materials), 150., 150.);
-    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Bishop, get_other_color(game.game.board().next_to_move()), &materials), -150., -150.);
-    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Knight, get_other_color(game.game.board().next_to_move()), &materials), 150., -150.);
+    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Queen, game.game.board().next_to_move(), &materials), -150., 150.);
+    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Rook, game.game.board().next_to_move(), &materials), 150., 150.);
+    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Bishop, game.game.board().next_to_move(), &materials), -150., -150.);
+    promotion_sprite_spawn(&mut commands, match_piece_to_sprite(Knight, game.game.board().next_to_move(), &materials), 150., -150.);
 }
 
 fn setup_system(
@@ -249,7 +257,10 @@ fn setup_system(
             game, 
             highlighted_piece: None,
             is_promoting: false,
-            promotion_pos: None
+            pending_promotion: None,
+            computer: None,
+            engine: Some(Engine::new(Limit::Nodes(200_000))),
+            search: None,
     });
 
     commands.insert_resource(WindowSize {
@@ -365,13 +376,63 @@ fn handle_mouse_clicks_system(
 ) {
     
     let window = windows.get_primary().expect("No Primary Window");
-    if mouse_input.just_pressed(MouseButton::Left) {
+    // Pieces can't be moved while the computer is thinking
+    if mouse_input.just_pressed(MouseButton::Left) && game.search.is_none() {
         handle_hightlights(&mut game, &mut materials, &mut commands, &mut highlighted_moves, &mut highlighted_piece, window);
         // Moves only click. No drag and drop
         handle_moves(window, game, commands, highlighted_moves, highlighted_piece);
     }
 }
 
+/// Takes back the last move on `U` and makes it again on `R`
+fn handle_takeback_system(keyboard_input: Res<Input<KeyCode>>, mut game: ResMut<Game>) {
+    if keyboard_input.just_pressed(KeyCode::U) {
+        if game.game.undo().is_some() {
+            game.highlighted_piece = None;
+            print!("{}", game.game.board().to_string());
+        }
+    } else if keyboard_input.just_pressed(KeyCode::R) {
+        if game.game.redo().is_some() {
+            game.highlighted_piece = None;
+            print!("{}", game.game.board().to_string());
+        }
+    }
+}
+
+/// Lets the computer play the player to move on `C`, and makes its moves
+fn handle_computer_system(keyboard_input: Res<Input<KeyCode>>, mut game: ResMut<Game>) {
+    if keyboard_input.just_pressed(KeyCode::C) {
+        game.computer = Some(game.game.board().next_to_move());
+    }
+
+    if let Some((board, search)) = game.search.take() {
+        if !search.is_finished() {
+            game.search = Some((board, search));
+            return;
+        }
+        let (engine, m) = search.join().unwrap();
+        game.engine = Some(engine);
+        // The move is thrown away if the board changed in the meantime, e.g.
+        // by an undo
+        if game.game.board() == &board && game.game.state().is_ongoing() {
+            game.highlighted_piece = None;
+            make_move(&mut game, m);
+        }
+        return;
+    }
+
+    let board = game.game.board().clone();
+    if game.computer == Some(board.next_to_move()) && game.game.state().is_ongoing() {
+        let mut engine = game.engine.take().unwrap();
+        let chess_game = game.game.clone();
+        let search = thread::spawn(move || {
+            let m = engine.search_game(&chess_game).best_move.unwrap();
+            (engine, m)
+        });
+        game.search = Some((board, search));
+    }
+}
+
 fn handle_moves(
     window: &Window,
     mut game: ResMut<Game>,
@@ -394,42 +455,46 @@ fn handle_moves(
     
     for dest_pos in moves {
         if cursor_pos == dest_pos {
-            println!("Good Move");
-            // Test
-            
-
             let m = Move::from((pos, dest_pos));
+            game.highlighted_piece = None;
 
-            match game.game.make_move(m, || Queen) {
-                Ok(GameState::Ongoing) => (),
-                Ok(GameState::Draw) => {
-                    println!("Draw!");
-                    return;
-                }
-                Ok(GameState::Checkmate { winner }) => {
-                    println!("Checkmate! {:?} wins", winner);
-                    return;
-                }
-                Err(err) => {
-                    println!("{}", err);
-                    continue;
-                }
-            };
+            // Pawns that change file capture, possibly en passant
+            let is_capture = b[dest_pos].is_some() || piece.kind == Pawn && pos.file() != dest_pos.file();
+            if is_capture && b.see(m) < 0 {
+                println!("Warning: {} loses material", m.as_arabic());
+            }
 
-            let destination_piece = game.game.board()[dest_pos].unwrap();
-            println!("new piece {:?}", destination_piece);
-            if piece.kind != destination_piece.kind {
-                // Promotion happend 
+            if piece.kind == Pawn && (dest_pos.rank() == 0 || dest_pos.rank() == 7) {
+                // Let the player pick a piece before making the move
                 game.is_promoting = true;
-                game.promotion_pos = Some(dest_pos);
+                game.pending_promotion = Some(m);
+                return;
             }
-            game.highlighted_piece = None;
-            print!("{}", game.game.board().to_string());
 
+            make_move(&mut game, m);
+            return;
         }
     }
 }
 
+fn make_move(game: &mut ResMut<Game>, m: Move) {
+    match game.game.make_move(m) {
+        Ok(GameState::Ongoing) => (),
+        Ok(state @ GameState::DrawClaimable(_)) => {
+            println!("{}", state);
+        }
+        Ok(state) => {
+            println!("{} ({})", state, state.result());
+            return;
+        }
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    print!("{}", game.game.board().to_string());
+}
+
 fn handle_hightlights(
     mut game: &mut ResMut<Game>,
     mut materials: &mut ResMut<Assets<ColorMaterial>>,
@@ -0,0 +1,129 @@
+use chess_engine::{
+    perft::{EpdEntry, Perft},
+    Board,
+};
+use std::{fs, process, thread, time::Instant};
+
+const USAGE: &str = "\
+Usage:
+    perft <depth> [fen]
+        Prints the number of leaf nodes after every legal move, the total,
+        the time taken and the nodes per second. Uses the default position
+        if no fen is given.
+    perft --suite <file> [max depth]
+        Runs every position in an EPD perft suite, e.g.
+        \"<fen> ;D1 20 ;D2 400\", and reports the ones that don't match.
+
+Options:
+    --threads <n>
+        Splits the moves from each position between n threads. Defaults to
+        the number of cores.
+    --hash <megabytes>
+        Uses a hash table of node counts of the given size. Off by default.";
+
+fn main() {
+    let result = options(std::env::args().skip(1).collect()).and_then(|(perft, args)| {
+        match args.first().map(String::as_str) {
+            Some("--suite") => match (args.get(1), args.get(2).map(|d| d.parse())) {
+                (Some(file), None) => suite(&perft, file, usize::MAX),
+                (Some(file), Some(Ok(max_depth))) => suite(&perft, file, max_depth),
+                _ => Err(USAGE.to_owned()),
+            },
+            Some(depth) => match depth.parse() {
+                Ok(depth) if args.len() > 1 => divide(&perft, depth, &args[1..].join(" ")),
+                Ok(depth) => divide(&perft, depth, &Board::default().to_fen()),
+                Err(_) => Err(USAGE.to_owned()),
+            },
+            None => Err(USAGE.to_owned()),
+        }
+    });
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+/// Removes `--threads` and `--hash` from `args`, returning a `Perft` set up
+/// with them and the remaining arguments
+fn options(mut args: Vec<String>) -> Result<(Perft, Vec<String>), String> {
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut hash = 0;
+    for (flag, value) in [("--threads", &mut threads), ("--hash", &mut hash)] {
+        if let Some(i) = args.iter().position(|arg| arg == flag) {
+            *value = args
+                .get(i + 1)
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| USAGE.to_owned())?;
+            args.drain(i..i + 2);
+        }
+    }
+    Ok((Perft::new().threads(threads).hash_table(hash), args))
+}
+
+fn divide(perft: &Perft, depth: usize, fen: &str) -> Result<(), String> {
+    let board = Board::from_fen(fen).map_err(|err| err.to_string())?;
+
+    let start = Instant::now();
+    let moves = perft.divide(&board, depth);
+    let elapsed = start.elapsed();
+
+    for (m, nodes) in &moves {
+        println!("{}: {}", m.as_arabic(), nodes);
+    }
+    let nodes = if depth == 0 {
+        1
+    } else {
+        moves.iter().map(|(_, nodes)| nodes).sum()
+    };
+    println!();
+    println!("Nodes: {}", nodes);
+    println!("Time: {:.3} s", elapsed.as_secs_f64());
+    println!(
+        "Nodes per second: {:.0}",
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn suite(perft: &Perft, file: &str, max_depth: usize) -> Result<(), String> {
+    let text = fs::read_to_string(file).map_err(|err| format!("{}: {}", file, err))?;
+
+    let start = Instant::now();
+    let mut total_nodes = 0;
+    let mut mismatches = 0;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = EpdEntry::parse(line).map_err(|err| format!("{}:{}: {}", file, i + 1, err))?;
+        for &(depth, expected) in entry.nodes.iter().filter(|(d, _)| *d <= max_depth) {
+            let nodes = perft.perft(&entry.board, depth);
+            total_nodes += nodes;
+            if nodes != expected {
+                mismatches += 1;
+                println!(
+                    "Mismatch on line {} at depth {}: expected {}, got {}",
+                    i + 1,
+                    depth,
+                    expected,
+                    nodes
+                );
+                println!("    {}", entry.board.to_fen());
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!("Nodes: {}", total_nodes);
+    println!("Time: {:.3} s", elapsed.as_secs_f64());
+    println!(
+        "Nodes per second: {:.0}",
+        total_nodes as f64 / elapsed.as_secs_f64()
+    );
+    if mismatches == 0 {
+        println!("All positions match");
+        Ok(())
+    } else {
+        Err(format!("{} mismatches", mismatches))
+    }
+}
@@ -1,64 +1,133 @@
-use chess_engine::{piece, Board, Game, GameState, Move, Position};
+use chess_engine::{
+    search::{Engine, Limit},
+    Board, Color, Game, GameState, Move, Position,
+};
 use std::{io::BufRead, str::FromStr};
 
+/// Prints `state` if there is anything to say about it, or that the player
+/// to move is in check. Returns whether the game is over.
+fn report(state: GameState, board: &Board) -> bool {
+    match state {
+        GameState::Ongoing if board.is_check() => println!("Check"),
+        GameState::Ongoing => {}
+        GameState::DrawClaimable(_) => println!("{}, type \"claim\" to do so", state),
+        _ => println!("{} ({})", state, state.result()),
+    }
+    !state.is_ongoing()
+}
+
+/// Searches for a move for the player to move and prints it
+fn computer_move(engine: &mut Engine, game: &Game) -> Move {
+    let m = engine
+        .search_game(game)
+        .best_move
+        .expect("no legal moves to choose from");
+    println!("Computer plays {}", m.as_algebraic(game.board()).unwrap());
+    m
+}
+
 fn main() {
-    let mut game = Game::new(Board::default());
-    print!("{}", game.board().to_string());
+    // The game starts from the position given as arguments, if any
+    let fen = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let board = if fen.is_empty() {
+        Board::default()
+    } else {
+        match Board::from_fen(&fen) {
+            Ok(board) => board,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        }
+    };
+    let mut game = Game::new(board);
+    let mut engine = Engine::new(Limit::Nodes(200_000));
+    let mut computer: Option<Color> = None;
+    print!("{}", game.board());
+    if report(game.state(), game.board()) {
+        return;
+    }
     let stdin = std::io::stdin();
-    let mut lines = stdin.lock().lines().map(|line| line.unwrap());
-    while let Some(line) = lines.next() {
+    for line in stdin.lock().lines().map(|line| line.unwrap()) {
         let line = line.trim();
-        if line.len() == 2 {
-            let pos = match Position::from_str(line) {
-                Ok(pos) => pos,
-                Err(err) => {
-                    println!("{}", err);
-                    continue;
-                }
+        let ending = match line {
+            "claim" => Some(game.claim_draw()),
+            "draw" => Some(game.agree_draw()),
+            "resign" => Some(game.resign(game.board().next_to_move())),
+            _ => None,
+        };
+        match ending {
+            Some(Ok(state)) => {
+                report(state, game.board());
+                return;
+            }
+            Some(Err(err)) => {
+                println!("{}", err);
+                continue;
+            }
+            None => {}
+        }
+        if line == "undo" || line == "redo" {
+            let done = if line == "undo" {
+                game.undo().is_some()
+            } else {
+                game.redo().is_some()
             };
-            match game.board()[pos] {
-                Some(piece) => println!(
-                    "{}",
-                    piece
-                        .moves(game.board(), pos)
-                        .fold(String::new(), |acc, p| format!("{} {}", acc, p))
-                ),
-                None => {}
+            if !done {
+                println!("Nothing to {}", line);
             }
+            print!("{}", game.board());
+            continue;
+        }
+        // Typing the position of a piece lists its moves. Otherwise, the
+        // line is a move in arabic notation or SAN
+        if let Some((piece, pos)) = Position::from_str(line)
+            .ok()
+            .and_then(|pos| game.board()[pos].map(|piece| (piece, pos)))
+        {
+            println!(
+                "{}",
+                piece
+                    .moves(game.board(), pos)
+                    .fold(String::new(), |acc, p| format!("{} {}", acc, p))
+            );
             continue;
         }
 
-        let m = match Move::arabic(line.trim()) {
-            Ok(m) => m,
-            Err(err) => {
-                println!("{}", err);
-                continue;
+        // "go" lets the computer make a move, "play" also lets it make the
+        // moves of that player from now on
+        let mut m = if line == "go" || line == "play" {
+            if line == "play" {
+                computer = Some(game.board().next_to_move());
+            }
+            computer_move(&mut engine, &game)
+        } else {
+            match Move::arabic(line).or_else(|_| Move::from_san(line, game.board())) {
+                Ok(m) => m,
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
             }
         };
 
-        match game.make_move(m, || loop {
-            break match piece::Kind::from_name(lines.next().unwrap().chars().next().unwrap()) {
-                Ok(kind) => kind,
+        loop {
+            match game.make_move(m) {
+                Ok(state) => {
+                    if report(state, game.board()) {
+                        return;
+                    }
+                }
                 Err(err) => {
                     println!("{}", err);
-                    continue;
+                    break;
                 }
             };
-        }) {
-            Ok(GameState::Ongoing) => (),
-            Ok(GameState::Draw) => {
-                println!("Draw!");
-                return;
-            }
-            Ok(GameState::Checkmate { winner }) => {
-                println!("Checkmate! {:?} wins", winner);
-                return;
-            }
-            Err(err) => {
-                println!("{}", err);
-                continue;
+            print!("{}", game.board());
+            if computer != Some(game.board().next_to_move()) {
+                break;
             }
-        };
-        print!("{}", game.board().to_string());
+            m = computer_move(&mut engine, &game);
+        }
     }
 }